@@ -0,0 +1,119 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::median::quantile;
+use super::state::StateRecord;
+
+// The P-squared algorithm (Jain and Chlamtac): five markers whose heights
+// are nudged along a parabola as values arrive, so constant memory.
+const P: f64 = 0.5;
+const DN: [f64; 5] = [0.0, P / 2.0, P, (1.0 + P) / 2.0, 1.0];
+
+#[derive(Clone)]
+#[derive(Default)]
+pub struct State {
+    // all values until we have five, then marker heights
+    q: Vec<f64>,
+    // marker positions, actual and desired, once we have five
+    n: Vec<f64>,
+    np: Vec<f64>,
+}
+
+impl State {
+    fn add(&mut self, x: f64) {
+        if self.n.is_empty() {
+            self.q.push(x);
+            if self.q.len() == 5 {
+                self.q.sort_by(|f1, f2| f1.partial_cmp(f2).unwrap());
+                self.n = vec![0.0, 1.0, 2.0, 3.0, 4.0];
+                self.np = vec![0.0, 2.0 * P, 4.0 * P, 2.0 + 2.0 * P, 4.0];
+            }
+            return;
+        }
+
+        let q = &mut self.q;
+        let n = &mut self.n;
+
+        let k;
+        if x < q[0] {
+            q[0] = x;
+            k = 0;
+        }
+        else if x >= q[4] {
+            q[4] = x;
+            k = 3;
+        }
+        else {
+            k = (1..5).find(|&i| x < q[i]).unwrap() - 1;
+        }
+
+        for i in (k + 1)..5 {
+            n[i] += 1.0;
+        }
+        for i in 0..5 {
+            self.np[i] += DN[i];
+        }
+
+        for i in 1..4 {
+            let d = self.np[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let qp = q[i] + d / (n[i + 1] - n[i - 1]) * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i]) + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                if q[i - 1] < qp && qp < q[i + 1] {
+                    q[i] = qp;
+                }
+                else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] += d * (q[j] - q[i]) / (n[j] - n[i]);
+                }
+                n[i] += d;
+            }
+        }
+    }
+
+    fn finish(mut self) -> f64 {
+        if self.n.is_empty() {
+            return quantile(&mut self.q, P);
+        }
+        return self.q[2];
+    }
+}
+
+impl StateRecord for State {
+    fn to_record(&self) -> Record {
+        return (self.q.clone(), self.n.clone(), self.np.clone()).to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let (q, n, np) = StateRecord::from_record(r);
+        return State {
+            q: q,
+            n: n,
+            np: np,
+        };
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = State;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["amedian", "approxmedian"];
+    }
+
+    fn add(state: &mut State, a: &Arc<str>, r: Record) {
+        state.add(r.get_path(a).coerce_f64());
+    }
+
+    fn finish(state: State, _a: &Arc<str>) -> Record {
+        return Record::from(state.finish());
+    }
+}
@@ -6,6 +6,7 @@ use std::hash::Hash;
 use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
+use super::state::StateRecord;
 
 #[derive(Clone)]
 pub struct DistinctSet<T> {
@@ -30,6 +31,20 @@ impl<T: Clone + Eq + Hash> DistinctSet<T> {
     }
 }
 
+impl<T: StateRecord + Clone + Eq + Hash> StateRecord for DistinctSet<T> {
+    fn to_record(&self) -> Record {
+        return self.v.to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let mut s = DistinctSet::default();
+        for t in Vec::<T>::from_record(r) {
+            s.add(t);
+        }
+        return s;
+    }
+}
+
 impl<T> IntoIterator for DistinctSet<T> {
     type Item = T;
     type IntoIter = std::vec::IntoIter<T>;
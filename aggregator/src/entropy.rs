@@ -0,0 +1,33 @@
+use record::Record;
+use registry::args::OneStringArgs;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = HashMap<Record, i64>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["entropy"];
+    }
+
+    fn add(state: &mut HashMap<Record, i64>, a: &Arc<str>, r: Record) {
+        *state.entry(r.get_path(a)).or_insert(0) += 1;
+    }
+
+    fn finish(state: HashMap<Record, i64>, _a: &Arc<str>) -> Record {
+        // Shannon entropy, in bits
+        let total = state.values().sum::<i64>() as f64;
+        let h = state.values().map(|&ct| {
+            let p = (ct as f64) / total;
+            return -p * p.log2();
+        }).sum::<f64>();
+        return Record::from(h);
+    }
+}
@@ -0,0 +1,68 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::reorder::Reorder;
+
+pub enum EwmaArgs {
+}
+
+impl RegistryArgs for EwmaArgs {
+    type Val = (f64, Arc<str>, Arc<str>);
+
+    fn argct() -> usize {
+        return 3;
+    }
+
+    fn parse(args: &[&str]) -> (f64, Arc<str>, Arc<str>) {
+        assert_eq!(3, args.len());
+        let half_life = args[0].parse::<f64>().unwrap();
+        assert!(half_life > 0.0);
+        return (half_life, Arc::from(&*args[1]), Arc::from(&*args[2]));
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+// Folds (t, v) into the running (t, average).
+fn step(state: &mut Option<(f64, f64)>, half_life: f64, t: f64, v: f64) {
+    let avg = match *state {
+        Some((t0, avg0)) => {
+            // Old average's weight halves every half life.
+            let w0 = 0.5f64.powf((t - t0) / half_life);
+            w0 * avg0 + (1.0 - w0) * v
+        }
+        None => v,
+    };
+    *state = Some((t, avg));
+}
+
+// Samples go through a Reorder so they needn't come quite in time order.
+impl AggregatorBe for ImplBe {
+    type Args = EwmaArgs;
+    type State = (Reorder, Option<(f64, f64)>);
+
+    fn names() -> Vec<&'static str> {
+        return vec!["ewma"];
+    }
+
+    fn add(state: &mut (Reorder, Option<(f64, f64)>), a: &(f64, Arc<str>, Arc<str>), r: Record) {
+        let t = r.get_path(&a.1).coerce_f64();
+        let v = r.get_path(&a.2).coerce_f64();
+        if let Some((t, v)) = state.0.push(t, v) {
+            step(&mut state.1, a.0, t, v);
+        }
+    }
+
+    fn finish(state: (Reorder, Option<(f64, f64)>), a: &(f64, Arc<str>, Arc<str>)) -> Record {
+        let (reorder, mut avg) = state;
+        for &(t, v) in reorder.pending() {
+            step(&mut avg, a.0, t, v);
+        }
+        return Record::from(avg.unwrap().1);
+    }
+}
@@ -0,0 +1,98 @@
+use executor::SharedStream;
+use record::Record;
+use record::RecordTrait;
+use registry::Registry;
+use super::AggregatorInbox;
+use super::BoxedAggregator;
+
+// Every aggregator also gets "<name>_expr,<code>,..." which runs code (as in
+// xform) on each record before the aggregator sees it, and "<name>_if,<code>,..."
+// which only lets records through for which code returns true, e.g.
+// "sum_if,{{status}} >= 500,bytes" or "avg_expr,{{d}} = {{end}} - {{start}},d".
+pub(crate) fn register(r: &mut Registry<BoxedAggregator>) {
+    r.add_wrapped("_expr", 1, |a, agg| {
+        return Box::new(ExprAggregator {
+            f: SharedStream::parse_inline(a[0], false),
+            agg: agg,
+        });
+    });
+    r.add_wrapped("_if", 1, |a, agg| {
+        return Box::new(IfAggregator {
+            f: SharedStream::parse_inline(a[0], true),
+            any: false,
+            agg: agg,
+        });
+    });
+}
+
+struct ExprAggregator {
+    f: SharedStream,
+    agg: BoxedAggregator,
+}
+
+impl AggregatorInbox for ExprAggregator {
+    fn add(&mut self, r: Record) {
+        self.agg.add(self.f.call(r));
+    }
+
+    fn finish(self: Box<Self>) -> Record {
+        return self.agg.finish();
+    }
+
+    fn box_clone(&self) -> BoxedAggregator {
+        return Box::new(ExprAggregator {
+            f: self.f.fresh(),
+            agg: self.agg.clone(),
+        });
+    }
+
+    fn save(&self) -> Record {
+        return self.agg.save();
+    }
+
+    fn load(&mut self, r: &Record) {
+        self.agg.load(r);
+    }
+}
+
+// Many aggregators can't finish having seen nothing, so if no record passes
+// the result is null instead.
+struct IfAggregator {
+    f: SharedStream,
+    any: bool,
+    agg: BoxedAggregator,
+}
+
+impl AggregatorInbox for IfAggregator {
+    fn add(&mut self, r: Record) {
+        if self.f.call(r.clone()).coerce_bool() {
+            self.any = true;
+            self.agg.add(r);
+        }
+    }
+
+    fn finish(self: Box<Self>) -> Record {
+        if !self.any {
+            return Record::null();
+        }
+        return self.agg.finish();
+    }
+
+    fn box_clone(&self) -> BoxedAggregator {
+        return Box::new(IfAggregator {
+            f: self.f.fresh(),
+            any: self.any,
+            agg: self.agg.clone(),
+        });
+    }
+
+    fn save(&self) -> Record {
+        return Record::from_vec(vec![Record::from(self.any), self.agg.save()]);
+    }
+
+    fn load(&mut self, r: &Record) {
+        let arr = r.expect_array();
+        self.any = arr[0].coerce_bool();
+        self.agg.load(&arr[1]);
+    }
+}
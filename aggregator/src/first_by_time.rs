@@ -0,0 +1,31 @@
+use record::F64SortDishonorProxy;
+use record::Record;
+use record::RecordTrait;
+use registry::args::TwoStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::lexical_max::MaxState;
+use super::lexical_min::ReverseOrd;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = TwoStringArgs;
+    type State = MaxState<ReverseOrd<F64SortDishonorProxy>>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["firstbytime"];
+    }
+
+    fn add(state: &mut MaxState<ReverseOrd<F64SortDishonorProxy>>, a: &(Arc<str>, Arc<str>), r: Record) {
+        let t = r.get_path(&a.0);
+        state.add(ReverseOrd(F64SortDishonorProxy(t.coerce_f64())), r.get_path(&a.1));
+    }
+
+    fn finish(state: MaxState<ReverseOrd<F64SortDishonorProxy>>, _a: &(Arc<str>, Arc<str>)) -> Record {
+        return state.finish();
+    }
+}
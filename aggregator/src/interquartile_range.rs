@@ -0,0 +1,30 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::median::quantile;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = Vec<f64>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["iqr"];
+    }
+
+    fn add(state: &mut Vec<f64>, a: &Arc<str>, r: Record) {
+        state.push(r.get_path(a).coerce_f64());
+    }
+
+    fn finish(mut state: Vec<f64>, _a: &Arc<str>) -> Record {
+        let q3 = quantile(&mut state, 0.75);
+        let q1 = quantile(&mut state, 0.25);
+        return Record::from(q3 - q1);
+    }
+}
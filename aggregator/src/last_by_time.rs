@@ -0,0 +1,30 @@
+use record::F64SortDishonorProxy;
+use record::Record;
+use record::RecordTrait;
+use registry::args::TwoStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::lexical_max::MaxState;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = TwoStringArgs;
+    type State = MaxState<F64SortDishonorProxy>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["lastbytime"];
+    }
+
+    fn add(state: &mut MaxState<F64SortDishonorProxy>, a: &(Arc<str>, Arc<str>), r: Record) {
+        let t = r.get_path(&a.0);
+        state.add(F64SortDishonorProxy(t.coerce_f64()), r.get_path(&a.1));
+    }
+
+    fn finish(state: MaxState<F64SortDishonorProxy>, _a: &(Arc<str>, Arc<str>)) -> Record {
+        return state.finish();
+    }
+}
@@ -5,6 +5,7 @@ use std::cmp::Ord;
 use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
+use super::state::StateRecord;
 
 #[derive(Clone)]
 pub struct MaxState<K>(Option<(K, Record)>);
@@ -30,6 +31,16 @@ impl<K: Ord> MaxState<K> {
     }
 }
 
+impl<K: StateRecord> StateRecord for MaxState<K> {
+    fn to_record(&self) -> Record {
+        return self.0.to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return MaxState(Option::from_record(r));
+    }
+}
+
 pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
 
 pub(crate) struct ImplBe;
@@ -7,6 +7,7 @@ use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
 use super::lexical_max::MaxState;
+use super::state::StateRecord;
 
 #[derive(Clone)]
 #[derive(Eq)]
@@ -25,6 +26,16 @@ impl<T: Ord> Ord for ReverseOrd<T> {
     }
 }
 
+impl<T: StateRecord> StateRecord for ReverseOrd<T> {
+    fn to_record(&self) -> Record {
+        return self.0.to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return ReverseOrd(T::from_record(r));
+    }
+}
+
 pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
 
 pub(crate) struct ImplBe;
@@ -5,6 +5,7 @@ use std::cmp::Ord;
 use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
+use super::state::StateRecord;
 
 #[derive(Clone)]
 pub struct PercentileState<K>(Vec<(K, Record)>);
@@ -30,6 +31,16 @@ impl<K: Ord> PercentileState<K> {
     }
 }
 
+impl<K: StateRecord> StateRecord for PercentileState<K> {
+    fn to_record(&self) -> Record {
+        return self.0.to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return PercentileState(Vec::from_record(r));
+    }
+}
+
 pub enum PercentileArgs {
 }
 
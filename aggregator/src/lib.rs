@@ -1,3 +1,4 @@
+extern crate executor;
 #[macro_use]
 extern crate lazy_static;
 extern crate misc;
@@ -10,10 +11,17 @@ use registry::Registrant;
 use registry::args::RegistryArgs;
 use std::sync::Arc;
 
+mod expr;
+mod reorder;
+
+pub mod state;
+use self::state::StateRecord;
+
 pub type BoxedAggregator = Box<AggregatorInbox>;
 
 registry! {
     BoxedAggregator,
+    approx_median,
     array,
     average,
     concat,
@@ -22,18 +30,30 @@ registry! {
     distinct_array,
     distinct_concat,
     distinct_count,
+    entropy,
+    ewma,
     first,
+    first_by_time,
     first_record,
+    geometric_mean,
+    harmonic_mean,
     hash,
+    interquartile_range,
+    kurtosis,
     last,
+    last_by_time,
     last_record,
     lexical_max,
     lexical_min,
     lexical_percentile,
     linear_regression,
     max,
+    median,
     min,
+    mode,
     percentile,
+    range,
+    rate,
     record_for_lexical_max,
     record_for_lexical_min,
     record_for_lexical_percentile,
@@ -41,13 +61,19 @@ registry! {
     record_for_min,
     record_for_percentile,
     records,
+    skewness,
     standard_deviation,
     sum,
+    time_weighted_average,
+    top_k,
+    top_k_exact,
+    variance,
+    => expr::register
 }
 
 trait AggregatorBe {
     type Args: RegistryArgs;
-    type State: Clone + Default + Send + Sync;
+    type State: Clone + Default + Send + Sync + StateRecord;
 
     fn names() -> Vec<&'static str>;
     fn add(state: &mut Self::State, a: &<Self::Args as RegistryArgs>::Val, r: Record);
@@ -58,6 +84,8 @@ pub trait AggregatorInbox: Send + Sync {
     fn add(&mut self, r: Record);
     fn finish(self: Box<Self>) -> Record;
     fn box_clone(&self) -> BoxedAggregator;
+    fn save(&self) -> Record;
+    fn load(&mut self, r: &Record);
 }
 
 impl Clone for BoxedAggregator {
@@ -87,6 +115,14 @@ impl<B: AggregatorBe + 'static> AggregatorInbox for AggregatorInboxImpl<B> {
             s: self.s.clone(),
         });
     }
+
+    fn save(&self) -> Record {
+        return self.s.to_record();
+    }
+
+    fn load(&mut self, r: &Record) {
+        self.s = B::State::from_record(r);
+    }
 }
 
 struct AggregatorRegistrant<B: AggregatorBe> {
@@ -107,3 +143,6 @@ impl<B: AggregatorBe + 'static> Registrant<BoxedAggregator> for AggregatorRegist
         });
     }
 }
+
+#[cfg(test)]
+mod tests;
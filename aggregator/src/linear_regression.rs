@@ -5,6 +5,7 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
+use super::state::StateRecord;
 
 #[derive(Clone)]
 #[derive(Default)]
@@ -17,6 +18,24 @@ pub struct State {
     sxy: f64,
 }
 
+impl StateRecord for State {
+    fn to_record(&self) -> Record {
+        return vec![self.s1, self.sx, self.sx2, self.sy, self.sy2, self.sxy].to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let v = Vec::<f64>::from_record(r);
+        return State {
+            s1: v[0],
+            sx: v[1],
+            sx2: v[2],
+            sy: v[3],
+            sy2: v[4],
+            sxy: v[5],
+        };
+    }
+}
+
 pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
 
 pub(crate) struct ImplBe;
@@ -0,0 +1,36 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+
+// Linearly interpolated quantile (prop in [0, 1]) of all values seen.
+pub fn quantile(state: &mut Vec<f64>, prop: f64) -> f64 {
+    state.sort_by(|f1, f2| f1.partial_cmp(f2).unwrap());
+    let pos = prop * ((state.len() - 1) as f64);
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    return state[lo] + (pos - (lo as f64)) * (state[hi] - state[lo]);
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = Vec<f64>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["median"];
+    }
+
+    fn add(state: &mut Vec<f64>, a: &Arc<str>, r: Record) {
+        state.push(r.get_path(a).coerce_f64());
+    }
+
+    fn finish(mut state: Vec<f64>, _a: &Arc<str>) -> Record {
+        return Record::from(quantile(&mut state, 0.5));
+    }
+}
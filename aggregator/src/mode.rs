@@ -0,0 +1,29 @@
+use record::Record;
+use registry::args::OneStringArgs;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = HashMap<Record, (i64, usize)>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["mode"];
+    }
+
+    fn add(state: &mut HashMap<Record, (i64, usize)>, a: &Arc<str>, r: Record) {
+        let i = state.len();
+        state.entry(r.get_path(a)).or_insert((0, i)).0 += 1;
+    }
+
+    fn finish(state: HashMap<Record, (i64, usize)>, _a: &Arc<str>) -> Record {
+        // ties go to whichever was seen first
+        return state.into_iter().min_by_key(|(_v, (ct, i))| (-ct, *i)).unwrap().0;
+    }
+}
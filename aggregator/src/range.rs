@@ -0,0 +1,30 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = Option<(f64, f64)>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["range"];
+    }
+
+    fn add(state: &mut Option<(f64, f64)>, a: &Arc<str>, r: Record) {
+        let v = r.get_path(a).coerce_f64();
+        let (min, max) = state.unwrap_or((v, v));
+        *state = Some((min.min(v), max.max(v)));
+    }
+
+    fn finish(state: Option<(f64, f64)>, _a: &Arc<str>) -> Record {
+        let (min, max) = state.unwrap();
+        return Record::from(max - min);
+    }
+}
@@ -0,0 +1,62 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::TwoStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::state::StateRecord;
+
+#[derive(Clone)]
+#[derive(Default)]
+pub struct State {
+    first: Option<(f64, f64)>,
+    last: Option<(f64, f64)>,
+}
+
+impl StateRecord for State {
+    fn to_record(&self) -> Record {
+        return (self.first, self.last).to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let (first, last) = StateRecord::from_record(r);
+        return State {
+            first: first,
+            last: last,
+        };
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = TwoStringArgs;
+    type State = State;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["rate"];
+    }
+
+    fn add(state: &mut State, a: &(Arc<str>, Arc<str>), r: Record) {
+        let t = r.get_path(&a.0).coerce_f64();
+        let v = r.get_path(&a.1).coerce_f64();
+        if state.first.map(|(t0, _v0)| t < t0).unwrap_or(true) {
+            state.first = Some((t, v));
+        }
+        if state.last.map(|(t1, _v1)| t >= t1).unwrap_or(true) {
+            state.last = Some((t, v));
+        }
+    }
+
+    fn finish(state: State, _a: &(Arc<str>, Arc<str>)) -> Record {
+        let (t0, v0) = state.first.unwrap();
+        let (t1, v1) = state.last.unwrap();
+        if t1 == t0 {
+            // not enough time to say anything
+            return Record::null();
+        }
+        return Record::from((v1 - v0) / (t1 - t0));
+    }
+}
@@ -0,0 +1,54 @@
+use record::Record;
+use super::state::StateRecord;
+
+// How many of the latest samples are held back before being folded in.
+const REORDER_WINDOW: usize = 64;
+
+// For time-series aggregators which fold (t, v) samples into a running state
+// in time order: samples may arrive out of order by up to REORDER_WINDOW
+// places and are still folded in time order.  Anything older than what's
+// already been folded is dropped.
+#[derive(Clone)]
+#[derive(Default)]
+pub struct Reorder {
+    pending: Vec<(f64, f64)>,
+    folded_t: Option<f64>,
+}
+
+impl Reorder {
+    // Returns the oldest sample once it's old enough to fold in.
+    pub fn push(&mut self, t: f64, v: f64) -> Option<(f64, f64)> {
+        if let Some(t0) = self.folded_t {
+            if t < t0 {
+                return None;
+            }
+        }
+        let idx = self.pending.iter().take_while(|(t2, _)| *t2 <= t).count();
+        self.pending.insert(idx, (t, v));
+        if self.pending.len() <= REORDER_WINDOW {
+            return None;
+        }
+        let e = self.pending.remove(0);
+        self.folded_t = Some(e.0);
+        return Some(e);
+    }
+
+    // Samples not yet folded in, in time order.
+    pub fn pending(&self) -> &[(f64, f64)] {
+        return &self.pending;
+    }
+}
+
+impl StateRecord for Reorder {
+    fn to_record(&self) -> Record {
+        return (self.pending.clone(), self.folded_t).to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let (pending, folded_t) = StateRecord::from_record(r);
+        return Reorder {
+            pending: pending,
+            folded_t: folded_t,
+        };
+    }
+}
@@ -0,0 +1,85 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::state::StateRecord;
+
+// Running central moments, updated one value at a time (Terriberry's
+// extension of Welford) to avoid the cancellation of raw power sums.
+#[derive(Clone)]
+#[derive(Default)]
+pub struct MomentsState {
+    n: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl MomentsState {
+    pub fn add(&mut self, x: f64) {
+        let n1 = self.n;
+        self.n += 1.0;
+        let n = self.n;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term1 = delta * delta_n * n1;
+        self.mean += delta_n;
+        self.m4 += term1 * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2 - 4.0 * delta_n * self.m3;
+        self.m3 += term1 * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term1;
+    }
+
+    pub fn variance(&self) -> f64 {
+        return self.m2 / self.n;
+    }
+
+    pub fn skewness(&self) -> f64 {
+        return self.n.sqrt() * self.m3 / self.m2.powf(1.5);
+    }
+
+    pub fn excess_kurtosis(&self) -> f64 {
+        return self.n * self.m4 / (self.m2 * self.m2) - 3.0;
+    }
+}
+
+impl StateRecord for MomentsState {
+    fn to_record(&self) -> Record {
+        return vec![self.n, self.mean, self.m2, self.m3, self.m4].to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let v = Vec::<f64>::from_record(r);
+        return MomentsState {
+            n: v[0],
+            mean: v[1],
+            m2: v[2],
+            m3: v[3],
+            m4: v[4],
+        };
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = MomentsState;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["skew", "skewness"];
+    }
+
+    fn add(state: &mut MomentsState, a: &Arc<str>, r: Record) {
+        state.add(r.get_path(a).coerce_f64());
+    }
+
+    fn finish(state: MomentsState, _a: &Arc<str>) -> Record {
+        return Record::from(state.skewness());
+    }
+}
@@ -0,0 +1,163 @@
+use misc::Either;
+use record::F64SortDishonorProxy;
+use record::Record;
+use record::RecordTrait;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+// Aggregator state as a record, e.g.  so it can be checkpointed and picked up
+// again later.  Maps are arrays of [k, v] since keys needn't be strings.
+pub trait StateRecord: Sized {
+    fn to_record(&self) -> Record;
+    fn from_record(r: &Record) -> Self;
+}
+
+impl StateRecord for Record {
+    fn to_record(&self) -> Record {
+        return self.clone();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.clone();
+    }
+}
+
+impl StateRecord for i64 {
+    fn to_record(&self) -> Record {
+        return Record::from(*self);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.coerce_num().map_right(|f| f as i64).join();
+    }
+}
+
+impl StateRecord for usize {
+    fn to_record(&self) -> Record {
+        return Record::from(*self as i64);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return i64::from_record(r) as usize;
+    }
+}
+
+impl StateRecord for f64 {
+    fn to_record(&self) -> Record {
+        return Record::from(*self);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.coerce_f64();
+    }
+}
+
+impl StateRecord for F64SortDishonorProxy {
+    fn to_record(&self) -> Record {
+        return Record::from(self.0);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return F64SortDishonorProxy(r.coerce_f64());
+    }
+}
+
+impl StateRecord for Either<i64, f64> {
+    fn to_record(&self) -> Record {
+        return self.clone().map_left(Record::from).map_right(Record::from).join();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.coerce_num();
+    }
+}
+
+impl StateRecord for Arc<str> {
+    fn to_record(&self) -> Record {
+        return Record::from(self.clone());
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.expect_string();
+    }
+}
+
+impl StateRecord for String {
+    fn to_record(&self) -> Record {
+        return Record::from(&self as &str);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.expect_string().to_string();
+    }
+}
+
+impl<T: StateRecord> StateRecord for Option<T> {
+    fn to_record(&self) -> Record {
+        return match self {
+            Some(t) => Record::from_vec(vec![t.to_record()]),
+            None => Record::null(),
+        };
+    }
+
+    fn from_record(r: &Record) -> Self {
+        if r.maybe_primitive().is_some() {
+            return None;
+        }
+        return Some(T::from_record(&r.expect_array()[0]));
+    }
+}
+
+impl<T: StateRecord> StateRecord for Vec<T> {
+    fn to_record(&self) -> Record {
+        return Record::from_vec(self.iter().map(T::to_record).collect());
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return r.expect_array().iter().map(T::from_record).collect();
+    }
+}
+
+impl<A: StateRecord, B: StateRecord> StateRecord for (A, B) {
+    fn to_record(&self) -> Record {
+        return Record::from_vec(vec![self.0.to_record(), self.1.to_record()]);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let arr = r.expect_array();
+        return (A::from_record(&arr[0]), B::from_record(&arr[1]));
+    }
+}
+
+impl<A: StateRecord, B: StateRecord, C: StateRecord> StateRecord for (A, B, C) {
+    fn to_record(&self) -> Record {
+        return Record::from_vec(vec![self.0.to_record(), self.1.to_record(), self.2.to_record()]);
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let arr = r.expect_array();
+        return (A::from_record(&arr[0]), B::from_record(&arr[1]), C::from_record(&arr[2]));
+    }
+}
+
+impl<K: StateRecord + Eq + Hash, V: StateRecord> StateRecord for HashMap<K, V> {
+    fn to_record(&self) -> Record {
+        return Record::from_vec(self.iter().map(|(k, v)| Record::from_vec(vec![k.to_record(), v.to_record()])).collect());
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return Vec::<(K, V)>::from_record(r).into_iter().collect();
+    }
+}
+
+impl<K: StateRecord + Ord, V: StateRecord> StateRecord for BTreeMap<K, V> {
+    fn to_record(&self) -> Record {
+        return Record::from_vec(self.iter().map(|(k, v)| Record::from_vec(vec![k.to_record(), v.to_record()])).collect());
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return Vec::<(K, V)>::from_record(r).into_iter().collect();
+    }
+}
@@ -5,6 +5,7 @@ use registry::args::OneStringArgs;
 use std::sync::Arc;
 use super::AggregatorBe;
 use super::AggregatorRegistrant;
+use super::state::StateRecord;
 
 pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
 
@@ -19,6 +20,16 @@ impl Default for State {
     }
 }
 
+impl StateRecord for State {
+    fn to_record(&self) -> Record {
+        return self.0.to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        return State(Either::from_record(r));
+    }
+}
+
 impl AggregatorBe for ImplBe {
     type Args = OneStringArgs;
     type State = State;
@@ -0,0 +1,181 @@
+use record::Record;
+use record::RecordTrait;
+use super::REGISTRY;
+
+fn test_one(name: &str, args: &[&str], rs: &[&str], o: &str) {
+    let mut agg = REGISTRY.find(name, args);
+    for r in rs {
+        agg.add(Record::parse(r));
+    }
+    assert_eq!(agg.finish().deparse(), o);
+}
+
+#[test]
+fn test_ewma_out_of_order() {
+    let rs = [r#"{"t":0,"v":0}"#, r#"{"t":2,"v":8}"#, r#"{"t":1,"v":4}"#];
+    // (0 -> 4 at half weight) = 2, (2 -> 8 at half weight) = 5
+    test_one("ewma", &["1", "t", "v"], &rs, "5.0");
+}
+
+#[test]
+fn test_twavg_out_of_order() {
+    let rs = [r#"{"t":3,"v":100}"#, r#"{"t":0,"v":1}"#, r#"{"t":2,"v":4}"#];
+    // 1 for 2 time units, then 4 for 1
+    test_one("twavg", &["t", "v"], &rs, "2.0");
+}
+
+#[test]
+fn test_if_none_pass() {
+    let rs = [r#"{"s":200,"b":"x"}"#, r#"{"s":404,"b":"y"}"#];
+    for name in &["max_if", "lmax_if", "median_if", "first_if", "range_if", "mode_if", "perc_if"] {
+        let args: &[&str] = match *name {
+            "perc_if" => &["{{s}} >= 500", "50", "b"],
+            _ => &["{{s}} >= 500", "b"],
+        };
+        test_one(name, args, &rs, "null");
+    }
+    test_one("ewma_if", &["{{s}} >= 500", "1", "s", "s"], &rs, "null");
+    test_one("count_if", &["{{s}} >= 400"], &rs, "1");
+}
+
+fn top_k_input() -> Vec<Record> {
+    // two heavy values among many more singletons than counters
+    return (0..300).map(|i| {
+        let v = match i {
+            i if i % 3 == 0 => "a".to_string(),
+            i if i % 5 == 0 => "b".to_string(),
+            i => format!("x{}", i),
+        };
+        let mut r = Record::empty_hash();
+        r.set_path("v", Record::from(v));
+        return r;
+    }).collect();
+}
+
+#[test]
+fn test_top_k_evictions() {
+    let rs = top_k_input();
+    let mut agg = REGISTRY.find("topk", &["2", "v"]);
+    for r in rs.iter() {
+        agg.add(r.clone());
+    }
+    let out = agg.finish();
+    let out = out.expect_array();
+    assert_eq!(out.len(), 2);
+    for (e, (v, truth)) in out.iter().zip(vec![("a", 100), ("b", 40)]) {
+        assert_eq!(e.get_path("value").deparse(), format!("\"{}\"", v));
+        // true count is between count - error and count
+        let ct = e.get_path("count").coerce_f64() as i64;
+        let err = e.get_path("error").coerce_f64() as i64;
+        assert!(ct - err <= truth && truth <= ct, "{} {} {} {}", v, ct, err, truth);
+    }
+}
+
+#[test]
+fn test_top_k_save_load() {
+    let rs = top_k_input();
+    let mut agg = REGISTRY.find("topk", &["2", "v"]);
+    for r in rs.iter() {
+        agg.add(r.clone());
+    }
+    let expected = agg.finish().deparse();
+
+    let mut agg = REGISTRY.find("topk", &["2", "v"]);
+    for r in rs[0..150].iter() {
+        agg.add(r.clone());
+    }
+    let saved = agg.save();
+    // evictions after loading need the rebuilt mins index
+    let mut agg = REGISTRY.find("topk", &["2", "v"]);
+    agg.load(&saved);
+    for r in rs[150..].iter() {
+        agg.add(r.clone());
+    }
+    assert_eq!(agg.finish().deparse(), expected);
+}
+
+#[test]
+fn test_ewma_twavg_too_late() {
+    let mut rs: Vec<_> = (1..101).map(|t| format!(r#"{{"t":{},"v":{}}}"#, t, t % 7)).collect();
+    let expected_ewma = {
+        let rs: Vec<_> = rs.iter().map(|r| r as &str).collect();
+        let mut agg = REGISTRY.find("ewma", &["10", "t", "v"]);
+        for r in rs {
+            agg.add(Record::parse(r));
+        }
+        agg.finish().deparse()
+    };
+    // older than anything still held back, so dropped
+    rs.push(r#"{"t":0,"v":1000}"#.to_string());
+    let rs: Vec<_> = rs.iter().map(|r| r as &str).collect();
+    test_one("ewma", &["10", "t", "v"], &rs, &expected_ewma);
+    // 1..=100 of v = t % 7 over 99 time units, the last value not counted
+    let integral: i64 = (1..100).map(|t| t % 7).sum();
+    test_one("twavg", &["t", "v"], &rs, &Record::from(integral as f64 / 99.0).deparse());
+}
+
+#[test]
+fn test_var_large_offset() {
+    test_one("var", &["x"], &[r#"{"x":1000000000}"#, r#"{"x":1000000001}"#, r#"{"x":1000000002}"#], &Record::from(2.0 / 3.0).deparse());
+}
+
+fn finish_f64(name: &str, xs: &[f64]) -> f64 {
+    let mut agg = REGISTRY.find(name, &["x"]);
+    for &x in xs {
+        let mut r = Record::empty_hash();
+        r.set_path("x", Record::from(x));
+        agg.add(r);
+    }
+    return agg.finish().coerce_f64();
+}
+
+#[test]
+fn test_quantiles() {
+    assert_eq!(finish_f64("median", &[3.0, 1.0, 2.0]), 2.0);
+    assert_eq!(finish_f64("median", &[4.0, 1.0, 3.0, 2.0]), 2.5);
+    assert_eq!(finish_f64("iqr", &[5.0, 1.0, 4.0, 2.0, 3.0]), 2.0);
+    // 3.25 - 1.75
+    assert_eq!(finish_f64("iqr", &[4.0, 1.0, 3.0, 2.0]), 1.5);
+}
+
+#[test]
+fn test_amedian() {
+    // fewer than five values is exact
+    assert_eq!(finish_f64("amedian", &[5.0, 1.0, 3.0]), 3.0);
+    assert_eq!(finish_f64("amedian", &[4.0, 1.0, 3.0, 2.0]), 2.5);
+
+    // 0..10001 scrambled, median 5000
+    let xs: Vec<_> = (0..10001).map(|i| ((i * 7919) % 10001) as f64).collect();
+    let m = finish_f64("amedian", &xs);
+    assert!((m - 5000.0).abs() < 100.0, "{}", m);
+}
+
+#[test]
+fn test_moments() {
+    // two pass population moments
+    let moments = |xs: &[f64]| {
+        let n = xs.len() as f64;
+        let mean = xs.iter().sum::<f64>() / n;
+        let m = |k| xs.iter().map(|x| (x - mean).powi(k)).sum::<f64>();
+        return (m(2) / n, n.sqrt() * m(3) / m(2).powf(1.5), n * m(4) / (m(2) * m(2)) - 3.0);
+    };
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9 * (1.0 + b.abs());
+
+    assert_eq!(finish_f64("skew", &[1.0, 2.0, 3.0]), 0.0);
+    for xs in &[vec![1.0, 2.0, 10.0], vec![3.0, -1.0, 4.0, 1.0, -5.0, 9.0, 2.0, 6.0]] {
+        let (var, skew, kurt) = moments(xs);
+        assert!(close(finish_f64("var", xs), var));
+        assert!(close(finish_f64("skew", xs), skew));
+        assert!(close(finish_f64("kurt", xs), kurt));
+    }
+}
+
+#[test]
+fn test_entropy() {
+    assert_eq!(finish_f64("entropy", &[1.0]), 0.0);
+    assert_eq!(finish_f64("entropy", &[1.0, 1.0, 2.0, 2.0]), 1.0);
+    assert_eq!(finish_f64("entropy", &[1.0, 2.0, 3.0, 4.0]), 2.0);
+    // -(3/4 log2 3/4 + 1/4 log2 1/4)
+    let h = finish_f64("entropy", &[1.0, 1.0, 1.0, 2.0]);
+    assert!((h - 0.8112781244591328).abs() < 1e-12);
+}
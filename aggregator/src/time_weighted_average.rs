@@ -0,0 +1,79 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::TwoStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::reorder::Reorder;
+use super::state::StateRecord;
+
+#[derive(Clone)]
+#[derive(Default)]
+pub struct State {
+    reorder: Reorder,
+    first_t: Option<f64>,
+    last: Option<(f64, f64)>,
+    integral: f64,
+}
+
+impl State {
+    fn step(&mut self, t: f64, v: f64) {
+        // Each value holds from its timestamp until the next one's.
+        if let Some((t0, v0)) = self.last {
+            self.integral += v0 * (t - t0);
+        }
+        self.first_t.get_or_insert(t);
+        self.last = Some((t, v));
+    }
+}
+
+impl StateRecord for State {
+    fn to_record(&self) -> Record {
+        return (self.reorder.clone(), (self.first_t, self.last), self.integral).to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let (reorder, (first_t, last), integral) = StateRecord::from_record(r);
+        return State {
+            reorder: reorder,
+            first_t: first_t,
+            last: last,
+            integral: integral,
+        };
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+// Samples go through a Reorder so they needn't come quite in time order.
+impl AggregatorBe for ImplBe {
+    type Args = TwoStringArgs;
+    type State = State;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["twavg"];
+    }
+
+    fn add(state: &mut State, a: &(Arc<str>, Arc<str>), r: Record) {
+        let t = r.get_path(&a.0).coerce_f64();
+        let v = r.get_path(&a.1).coerce_f64();
+        if let Some((t, v)) = state.reorder.push(t, v) {
+            state.step(t, v);
+        }
+    }
+
+    fn finish(mut state: State, _a: &(Arc<str>, Arc<str>)) -> Record {
+        let pending = state.reorder.pending().to_vec();
+        for (t, v) in pending {
+            state.step(t, v);
+        }
+        let t0 = state.first_t.unwrap();
+        let (t1, v1) = state.last.unwrap();
+        if t1 == t0 {
+            return Record::from(v1);
+        }
+        return Record::from(state.integral / (t1 - t0));
+    }
+}
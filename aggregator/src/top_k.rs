@@ -0,0 +1,115 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::RegistryArgs;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::state::StateRecord;
+
+pub enum TopKArgs {
+}
+
+impl RegistryArgs for TopKArgs {
+    type Val = (usize, Arc<str>);
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> (usize, Arc<str>) {
+        assert_eq!(2, args.len());
+        let n = args[0].parse::<usize>().unwrap();
+        assert!(n > 0);
+        return (n, Arc::from(&*args[1]));
+    }
+}
+
+// (value, count, error) in order of decreasing count, ties broken by i.
+pub fn finish_top_k(n: usize, mut v: Vec<(Record, i64, i64, usize)>) -> Record {
+    v.sort_by_key(|(_v, ct, _err, i)| (-ct, *i));
+    return Record::from_vec(v.into_iter().take(n).map(|(v, ct, err, _i)| {
+        let mut r = Record::empty_hash();
+        r.set_path("value", v);
+        r.set_path("count", Record::from(ct));
+        r.set_path("error", Record::from(err));
+        return r;
+    }).collect());
+}
+
+// How many more counters than requested results we track.  Space-Saving
+// guarantees any value with count over total / counters is tracked.
+const CAPACITY_FACTOR: usize = 10;
+
+#[derive(Clone)]
+#[derive(Default)]
+pub struct SpaceSavingState {
+    counts: HashMap<Record, (i64, i64, usize)>,
+    mins: BTreeMap<(i64, usize), Record>,
+    i: usize,
+}
+
+impl SpaceSavingState {
+    fn add(&mut self, capacity: usize, v: Record) {
+        if let Some((ct, _err, i)) = self.counts.get_mut(&v) {
+            self.mins.remove(&(*ct, *i));
+            *ct += 1;
+            self.mins.insert((*ct, *i), v);
+            return;
+        }
+
+        let i = self.i;
+        self.i += 1;
+
+        let mut ct = 1;
+        let mut err = 0;
+        if self.counts.len() >= capacity {
+            let (&(min_ct, min_i), _) = self.mins.iter().next().unwrap();
+            let evicted = self.mins.remove(&(min_ct, min_i)).unwrap();
+            self.counts.remove(&evicted);
+            ct = min_ct + 1;
+            err = min_ct;
+        }
+
+        self.counts.insert(v.clone(), (ct, err, i));
+        self.mins.insert((ct, i), v);
+    }
+}
+
+impl StateRecord for SpaceSavingState {
+    fn to_record(&self) -> Record {
+        return (self.counts.clone(), self.i).to_record();
+    }
+
+    fn from_record(r: &Record) -> Self {
+        let (counts, i): (HashMap<Record, (i64, i64, usize)>, usize) = StateRecord::from_record(r);
+        let mins = counts.iter().map(|(v, (ct, _err, i))| ((*ct, *i), v.clone())).collect();
+        return SpaceSavingState {
+            counts: counts,
+            mins: mins,
+            i: i,
+        };
+    }
+}
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = TopKArgs;
+    type State = SpaceSavingState;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["topk"];
+    }
+
+    fn add(state: &mut SpaceSavingState, a: &(usize, Arc<str>), r: Record) {
+        state.add(a.0 * CAPACITY_FACTOR, r.get_path(&a.1));
+    }
+
+    fn finish(state: SpaceSavingState, a: &(usize, Arc<str>)) -> Record {
+        return finish_top_k(a.0, state.counts.into_iter().map(|(v, (ct, err, i))| (v, ct, err, i)).collect());
+    }
+}
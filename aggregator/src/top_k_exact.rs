@@ -0,0 +1,29 @@
+use record::Record;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::top_k::TopKArgs;
+use super::top_k::finish_top_k;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = TopKArgs;
+    type State = HashMap<Record, (i64, usize)>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["topkexact", "xtopk"];
+    }
+
+    fn add(state: &mut HashMap<Record, (i64, usize)>, a: &(usize, Arc<str>), r: Record) {
+        let i = state.len();
+        state.entry(r.get_path(&a.1)).or_insert((0, i)).0 += 1;
+    }
+
+    fn finish(state: HashMap<Record, (i64, usize)>, a: &(usize, Arc<str>)) -> Record {
+        return finish_top_k(a.0, state.into_iter().map(|(v, (ct, i))| (v, ct, 0, i)).collect());
+    }
+}
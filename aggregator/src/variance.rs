@@ -0,0 +1,28 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::OneStringArgs;
+use std::sync::Arc;
+use super::AggregatorBe;
+use super::AggregatorRegistrant;
+use super::skewness::MomentsState;
+
+pub(crate) type Impl = AggregatorRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe;
+
+impl AggregatorBe for ImplBe {
+    type Args = OneStringArgs;
+    type State = MomentsState;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["var", "variance"];
+    }
+
+    fn add(state: &mut MomentsState, a: &Arc<str>, r: Record) {
+        state.add(r.get_path(a).coerce_f64());
+    }
+
+    fn finish(state: MomentsState, _a: &Arc<str>) -> Record {
+        return Record::from(state.variance());
+    }
+}
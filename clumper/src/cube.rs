@@ -0,0 +1,44 @@
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::rollup::RollupSpec;
+
+pub enum CubeArgs {
+}
+
+// "cube,k1:k2:...[,all]" with all (default "ALL") filling rolled up keys
+impl RegistryArgs for CubeArgs {
+    type Val = RollupSpec;
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn max_argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> RollupSpec {
+        assert!(args.len() == 1 || args.len() == 2);
+        return RollupSpec::parse(args[0], args.get(1).cloned().unwrap_or("ALL"), true);
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = CubeArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["cube"];
+    }
+
+    fn stream(spec: &RollupSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::rollup::stream(spec, bsw);
+    }
+}
@@ -0,0 +1,30 @@
+use record::Record;
+
+// Durations as seconds, e.g.  "90", "1.5s", "500ms", "5m", "2h", "1d".
+pub fn parse_duration(s: &str) -> f64 {
+    let units: &[(&str, f64)] = &[
+        ("ms", 0.001),
+        ("s", 1.0),
+        ("m", 60.0),
+        ("h", 3600.0),
+        ("d", 86400.0),
+        ("w", 604800.0),
+    ];
+    for (suffix, mult) in units {
+        if s.ends_with(suffix) {
+            if let Ok(n) = s[0..(s.len() - suffix.len())].parse::<f64>() {
+                return n * mult;
+            }
+        }
+    }
+    return s.parse::<f64>().unwrap_or_else(|_| panic!("Unparseable duration {}", s));
+}
+
+// Times we compute (e.g.  window edges) as integers where possible so they
+// look like the input timestamps usually do.
+pub fn time_record(t: f64) -> Record {
+    if t.fract() == 0.0 && t.abs() < 9e15 {
+        return Record::from(t as i64);
+    }
+    return Record::from(t);
+}
@@ -0,0 +1,92 @@
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+
+pub enum HashPartitionArgs {
+}
+
+impl RegistryArgs for HashPartitionArgs {
+    type Val = (usize, Vec<Arc<str>>);
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    // Keys are separated by ':' since ',' separates the clumper's args.
+    fn parse(args: &[&str]) -> (usize, Vec<Arc<str>>) {
+        assert_eq!(2, args.len());
+        let n: usize = args[0].parse().unwrap();
+        assert!(n > 0, "hashpart needs a positive count");
+        return (n, args[1].split(':').map(Arc::from).collect());
+    }
+}
+
+// FNV-1a over the JSON of the key values so partitions are the same from run
+// to run (and build to build), unlike std's hashers, finished with a
+// splitmix64 step since FNV alone divides poorly by small moduli.
+fn stable_hash(vs: &[Record]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for v in vs {
+        for b in v.deparse().bytes().chain(std::iter::once(0u8)) {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x100000001b3);
+        }
+    }
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return h ^ (h >> 31);
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = HashPartitionArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["hashpart", "hash-partition"];
+    }
+
+    fn stream(a: &(usize, Vec<Arc<str>>), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        let (n, ref keys) = *a;
+        let keys = keys.clone();
+        let substreams: Vec<_> = (0..n).map(|i| bsw(vec![(Arc::from("partition"), Record::from(i as i64))])).collect();
+
+        return stream::compound(
+            stream::parse(),
+            stream::closures(
+                substreams,
+                move |s, e, w| {
+                    match e {
+                        Entry::Bof(_file) => {
+                            return true;
+                        },
+                        Entry::Record(r) => {
+                            let vs: Vec<_> = keys.iter().map(|k| r.get_path(k)).collect();
+                            let i = (stable_hash(&vs) % (s.len() as u64)) as usize;
+
+                            // Again, substream ending does not concern us, we
+                            // may need to truck on for other streams.
+                            s[i].write(Entry::Record(r), w);
+
+                            return true;
+                        },
+                        Entry::Line(_line) => {
+                            panic!("Unexpected line in HashPartitionStream");
+                        },
+                    }
+                },
+                |s, w| {
+                    for substream in s.into_iter() {
+                        substream.close(w);
+                    }
+                },
+            ),
+        );
+    }
+}
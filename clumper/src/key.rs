@@ -1,12 +1,26 @@
+use record::KeyNorm;
 use record::Record;
+use record::RecordTrait;
 use registry::args::OneStringArgs;
+use sorts::BoxedSort;
+use sorts::bucket::VecDequeSortBucket;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 use stream::Entry;
 use stream::Stream;
 use super::ClumperBe;
 use super::ClumperRegistrant;
 
+// Order in which the substreams are closed (and so typically in which their
+// output comes out).
+#[derive(Clone)]
+pub enum KeyOrder {
+    FirstSeen,
+    Hash,
+    Sort(BoxedSort),
+}
+
 pub type Impl = ClumperRegistrant<ImplBe>;
 
 pub struct ImplBe();
@@ -19,42 +33,83 @@ impl ClumperBe for ImplBe {
     }
 
     fn stream(k: &Arc<str>, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
-        let k = k.clone();
-
-        return stream::compound(
-            stream::parse(),
-            stream::closures(
-                HashMap::new(),
-                move |s, e, w| {
-                    match e {
-                        Entry::Bof(_file) => {
-                            return true;
-                        },
-                        Entry::Record(r) => {
-                            let v = r.get_path(&k);
-
-                            let substream = s.entry(v.clone()).or_insert_with(|| {
-                                return bsw(vec![(k.clone(), v)]);
-                            });
-
-                            // Disregard flow since one substream ending does
-                            // not mean we're done (e.g.  each substream could
-                            // be head -n 1).
-                            substream.write(Entry::Record(r), w);
-
-                            return true;
-                        },
-                        Entry::Line(_line) => {
-                            panic!("Unexpected line in KeyStream");
-                        },
-                    }
-                },
-                |s, w| {
-                    for (_, substream) in s.into_iter() {
-                        substream.close(w);
-                    }
-                },
-            ),
-        );
+        return stream(k, &KeyNorm::default(), &KeyOrder::FirstSeen, bsw);
+    }
+}
+
+// Key values are normalized by norm (both for grouping and in the bucket).
+pub fn stream(k: &Arc<str>, norm: &KeyNorm, order: &KeyOrder, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+    struct State {
+        idxs: HashMap<Record, usize>,
+        substreams: Vec<(Record, Stream)>,
     }
+
+    let k = k.clone();
+    let k2 = k.clone();
+    let norm = norm.clone();
+    let order = order.clone();
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                idxs: HashMap::new(),
+                substreams: Vec::new(),
+            },
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    },
+                    Entry::Record(r) => {
+                        let v = norm.apply(r.get_path(&k));
+
+                        let substreams = &mut s.substreams;
+                        let idx = *s.idxs.entry(v.clone()).or_insert_with(|| {
+                            substreams.push((v.clone(), bsw(vec![(k.clone(), v)])));
+                            return substreams.len() - 1;
+                        });
+
+                        // Disregard flow since one substream ending does not
+                        // mean we're done (e.g.  each substream could be head
+                        // -n 1).
+                        substreams[idx].1.write(Entry::Record(r), w);
+
+                        return true;
+                    },
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in KeyStream");
+                    },
+                }
+            },
+            move |s, w| {
+                let State { idxs, substreams } = s;
+                match order {
+                    KeyOrder::FirstSeen => {
+                        for (_, substream) in substreams.into_iter() {
+                            substream.close(w);
+                        }
+                    },
+                    KeyOrder::Hash => {
+                        let mut substreams: Vec<_> = substreams.into_iter().map(Some).collect();
+                        for (_, idx) in idxs.into_iter() {
+                            substreams[idx].take().unwrap().1.close(w);
+                        }
+                    },
+                    KeyOrder::Sort(ref sort) => {
+                        let mut bucket = sort.new_bucket(Rc::new(VecDequeSortBucket::new));
+                        let mut substreams: Vec<_> = substreams.into_iter().map(Some).collect();
+                        for (idx, e) in substreams.iter().enumerate() {
+                            let mut r = Record::empty_hash();
+                            r.set_path(&k2, e.as_ref().unwrap().0.clone());
+                            bucket.add(r, idx);
+                        }
+                        while let Some((_, idx)) = bucket.remove_first() {
+                            substreams[idx].take().unwrap().1.close(w);
+                        }
+                    },
+                }
+            },
+        ),
+    );
 }
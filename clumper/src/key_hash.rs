@@ -0,0 +1,25 @@
+use record::KeyNorm;
+use record::Record;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::key::KeyOrder;
+use super::key_norm::KeyOptNormArgs;
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+// Like key, but closes substreams in whatever order they hash to.
+impl ClumperBe for ImplBe {
+    type Args = KeyOptNormArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["khash", "key-hash"];
+    }
+
+    fn stream(a: &(Arc<str>, KeyNorm), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::key::stream(&a.0, &a.1, &KeyOrder::Hash, bsw);
+    }
+}
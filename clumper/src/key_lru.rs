@@ -0,0 +1,116 @@
+use record::KeyNorm;
+use record::Record;
+use registry::args::RegistryArgs;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+
+pub enum KeyLruArgs {
+}
+
+// "klru,x,n" with an optional third arg normalizing x as knorm does, e.g.
+// "klru,x,100,trim:ci".
+impl RegistryArgs for KeyLruArgs {
+    type Val = (Arc<str>, usize, KeyNorm);
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn max_argct() -> usize {
+        return 3;
+    }
+
+    fn parse(args: &[&str]) -> (Arc<str>, usize, KeyNorm) {
+        assert!(args.len() == 2 || args.len() == 3);
+        let norm = args.get(2).map(|a| KeyNorm::parse(a)).unwrap_or_default();
+        return (Arc::from(args[0]), args[1].parse().unwrap(), norm);
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+// Like key, but with at most n substreams open at once, closing the least
+// recently used when over.  Note a key that comes back after being evicted
+// gets a fresh substream (and so e.g.  a second aggregate output).
+impl ClumperBe for ImplBe {
+    type Args = KeyLruArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["klru", "key-lru"];
+    }
+
+    fn stream(a: &(Arc<str>, usize, KeyNorm), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        struct State {
+            substreams: HashMap<Record, (Stream, u64)>,
+            lru: BTreeMap<u64, Record>,
+            n: u64,
+        }
+
+        let (k, size, norm) = a.clone();
+        assert!(size > 0, "klru needs a positive size");
+
+        return stream::compound(
+            stream::parse(),
+            stream::closures(
+                State {
+                    substreams: HashMap::new(),
+                    lru: BTreeMap::new(),
+                    n: 0,
+                },
+                move |s, e, w| {
+                    match e {
+                        Entry::Bof(_file) => {
+                            return true;
+                        },
+                        Entry::Record(r) => {
+                            let v = norm.apply(r.get_path(&k));
+
+                            let n = s.n;
+                            s.n += 1;
+
+                            {
+                                let lru = &mut s.lru;
+                                let substream = s.substreams.entry(v.clone()).or_insert_with(|| {
+                                    return (bsw(vec![(k.clone(), v.clone())]), n);
+                                });
+                                lru.remove(&substream.1);
+                                substream.1 = n;
+                                lru.insert(n, v);
+
+                                // Disregard flow since one substream ending
+                                // does not mean we're done.
+                                substream.0.write(Entry::Record(r), w);
+                            }
+
+                            if s.substreams.len() > size {
+                                let n0 = *s.lru.keys().next().unwrap();
+                                let v0 = s.lru.remove(&n0).unwrap();
+                                let (substream, _) = s.substreams.remove(&v0).unwrap();
+                                substream.close(w);
+                            }
+
+                            return true;
+                        },
+                        Entry::Line(_line) => {
+                            panic!("Unexpected line in KeyLruStream");
+                        },
+                    }
+                },
+                |s, w| {
+                    let State { mut substreams, lru, .. } = s;
+                    for (_, v) in lru.into_iter() {
+                        let (substream, _) = substreams.remove(&v).unwrap();
+                        substream.close(w);
+                    }
+                },
+            ),
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use record::KeyNorm;
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::key::KeyOrder;
+
+pub enum KeyNormArgs {
+}
+
+// "knorm,x,num:ci" groups by x with numbers canonicalized and strings
+// lowercased (see KeyNorm for the steps).
+impl RegistryArgs for KeyNormArgs {
+    type Val = (Arc<str>, KeyNorm);
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> (Arc<str>, KeyNorm) {
+        assert_eq!(2, args.len());
+        return (Arc::from(args[0]), KeyNorm::parse(args[1]));
+    }
+}
+
+pub enum KeyOptNormArgs {
+}
+
+// For the other key clumpers, e.g.  "khash,x" or "khash,x,num:ci".
+impl RegistryArgs for KeyOptNormArgs {
+    type Val = (Arc<str>, KeyNorm);
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn max_argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> (Arc<str>, KeyNorm) {
+        assert!(args.len() == 1 || args.len() == 2);
+        return (Arc::from(args[0]), args.get(1).map(|a| KeyNorm::parse(a)).unwrap_or_default());
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = KeyNormArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["knorm", "key-norm"];
+    }
+
+    fn stream(a: &(Arc<str>, KeyNorm), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::key::stream(&a.0, &a.1, &KeyOrder::FirstSeen, bsw);
+    }
+}
@@ -0,0 +1,69 @@
+use record::KeyNorm;
+use record::Record;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::key_norm::KeyOptNormArgs;
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+// Like key, but for input already grouped by key: each contiguous run of the
+// same value gets its own substream which is closed as soon as the value
+// changes.
+impl ClumperBe for ImplBe {
+    type Args = KeyOptNormArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["krun", "key-run"];
+    }
+
+    fn stream(a: &(Arc<str>, KeyNorm), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        let (k, norm) = a.clone();
+
+        return stream::compound(
+            stream::parse(),
+            stream::closures(
+                None,
+                move |s: &mut Option<(Record, Stream)>, e, w| {
+                    match e {
+                        Entry::Bof(_file) => {
+                            return true;
+                        },
+                        Entry::Record(r) => {
+                            let v = norm.apply(r.get_path(&k));
+
+                            let same = match s {
+                                Some((v0, _)) => *v0 == v,
+                                None => false,
+                            };
+                            if !same {
+                                if let Some((_, substream)) = s.take() {
+                                    substream.close(w);
+                                }
+                                *s = Some((v.clone(), bsw(vec![(k.clone(), v)])));
+                            }
+
+                            // Disregard flow since one substream ending does
+                            // not mean we're done.
+                            s.as_mut().unwrap().1.write(Entry::Record(r), w);
+
+                            return true;
+                        },
+                        Entry::Line(_line) => {
+                            panic!("Unexpected line in KeyRunStream");
+                        },
+                    }
+                },
+                |s, w| {
+                    if let Some((_, substream)) = s {
+                        substream.close(w);
+                    }
+                },
+            ),
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::duration::parse_duration;
+use super::session::SessionSpec;
+
+pub enum KeySessionArgs {
+}
+
+impl RegistryArgs for KeySessionArgs {
+    type Val = SessionSpec;
+
+    fn argct() -> usize {
+        return 3;
+    }
+
+    fn parse(args: &[&str]) -> SessionSpec {
+        assert_eq!(3, args.len());
+        return SessionSpec {
+            k: Some(Arc::from(args[0])),
+            ts: Arc::from(args[1]),
+            gap: parse_duration(args[2]),
+        };
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+// Like "-k <key> -c session,<ts>,<gap>" except that since one stream sees
+// every key, sessions for keys that go quiet are closed as the stream as a
+// whole moves on rather than waiting for the key's next record.
+impl ClumperBe for ImplBe {
+    type Args = KeySessionArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["ksession", "key-session"];
+    }
+
+    fn stream(spec: &SessionSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::session::stream(spec, bsw);
+    }
+}
@@ -0,0 +1,53 @@
+use record::KeyNorm;
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::key::KeyOrder;
+
+pub enum KeySortArgs {
+}
+
+// "ksort,x,numeric" closes in order of x as sorted by "numeric,x" and
+// "ksort,x,-numeric" in reverse.  An optional third arg normalizes x as knorm
+// does, e.g.  "ksort,x,lexical,trim:ci".
+impl RegistryArgs for KeySortArgs {
+    type Val = (Arc<str>, KeyOrder, KeyNorm);
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn max_argct() -> usize {
+        return 3;
+    }
+
+    fn parse(args: &[&str]) -> (Arc<str>, KeyOrder, KeyNorm) {
+        assert!(args.len() == 2 || args.len() == 3);
+        let k = args[0];
+        let sort = match args[1].starts_with('-') {
+            true => sorts::REGISTRY.find(&args[1][1..], &[&format!("-{}", k)]),
+            false => sorts::REGISTRY.find(args[1], &[k]),
+        };
+        let norm = args.get(2).map(|a| KeyNorm::parse(a)).unwrap_or_default();
+        return (Arc::from(k), KeyOrder::Sort(sort), norm);
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = KeySortArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["ksort", "key-sort"];
+    }
+
+    fn stream(a: &(Arc<str>, KeyOrder, KeyNorm), bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::key::stream(&a.0, &a.2, &a.1, bsw);
+    }
+}
@@ -3,8 +3,11 @@ extern crate lazy_static;
 extern crate record;
 #[macro_use]
 extern crate registry;
+extern crate sorts;
 extern crate stream;
 
+pub mod duration;
+
 use record::Record;
 use registry::Registrant;
 use registry::args::RegistryArgs;
@@ -16,8 +19,20 @@ pub type BoxedClumper = Box<ClumperInbox>;
 
 registry! {
     BoxedClumper,
+    cube,
+    hash_partition,
     key,
+    key_hash,
+    key_lru,
+    key_norm,
+    key_run,
+    key_session,
+    key_sort,
+    rollup,
     round_robin,
+    session,
+    sliding_window,
+    time_window,
     window,
 }
 
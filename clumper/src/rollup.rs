@@ -0,0 +1,132 @@
+use record::Record;
+use registry::args::RegistryArgs;
+use std::collections::HashMap;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+
+#[derive(Clone)]
+pub struct RollupSpec {
+    pub keys: Vec<Arc<str>>,
+    pub all: Record,
+    pub cube: bool,
+}
+
+impl RollupSpec {
+    // Keys are separated by ':' since ',' separates the clumper's args.
+    pub fn parse(keys: &str, all: &str, cube: bool) -> RollupSpec {
+        return RollupSpec {
+            keys: keys.split(':').map(Arc::from).collect(),
+            all: Record::from(all),
+            cube: cube,
+        };
+    }
+
+    // Which keys are kept (as opposed to replaced with the "all" value) in
+    // each of the buckets a record goes in: every prefix for rollup and every
+    // subset for cube.
+    fn masks(&self) -> Vec<Vec<bool>> {
+        let n = self.keys.len();
+        if self.cube {
+            assert!(n < 20, "Too many keys for cube");
+            return (0..(1usize << n)).rev().map(|m| (0..n).map(|i| m & (1 << (n - 1 - i)) != 0).collect()).collect();
+        }
+        return (0..=n).rev().map(|m| (0..n).map(|i| i < m).collect()).collect();
+    }
+}
+
+// Each record goes into one bucket per mask, e.g.  rollup,dc:host puts
+// {dc: d, host: h} into (d, h), (d, ALL), and (ALL, ALL).
+pub fn stream(spec: &RollupSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+    struct State {
+        idxs: HashMap<Vec<Record>, usize>,
+        substreams: Vec<Stream>,
+    }
+
+    let spec = spec.clone();
+    let masks = spec.masks();
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                idxs: HashMap::new(),
+                substreams: Vec::new(),
+            },
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    },
+                    Entry::Record(r) => {
+                        let vs: Vec<_> = spec.keys.iter().map(|k| r.get_path(k)).collect();
+
+                        for mask in masks.iter() {
+                            let vs: Vec<_> = vs.iter().zip(mask.iter()).map(|(v, &keep)| if keep { v.clone() } else { spec.all.clone() }).collect();
+
+                            let substreams = &mut s.substreams;
+                            let idx = *s.idxs.entry(vs.clone()).or_insert_with(|| {
+                                let bucket = spec.keys.iter().cloned().zip(vs.into_iter()).collect();
+                                substreams.push(bsw(bucket));
+                                return substreams.len() - 1;
+                            });
+
+                            // Disregard flow since one substream ending does
+                            // not mean we're done.
+                            substreams[idx].write(Entry::Record(r.clone()), w);
+                        }
+
+                        return true;
+                    },
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in RollupStream");
+                    },
+                }
+            },
+            |s, w| {
+                for substream in s.substreams.into_iter() {
+                    substream.close(w);
+                }
+            },
+        ),
+    );
+}
+
+pub enum RollupArgs {
+}
+
+// "rollup,k1:k2:...[,all]" with all (default "ALL") filling rolled up keys
+impl RegistryArgs for RollupArgs {
+    type Val = RollupSpec;
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn max_argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> RollupSpec {
+        assert!(args.len() == 1 || args.len() == 2);
+        return RollupSpec::parse(args[0], args.get(1).cloned().unwrap_or("ALL"), false);
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = RollupArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["rollup"];
+    }
+
+    fn stream(spec: &RollupSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return stream(spec, bsw);
+    }
+}
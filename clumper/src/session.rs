@@ -0,0 +1,192 @@
+use record::F64SortDishonorProxy;
+use record::Record;
+use record::RecordTrait;
+use registry::args::RegistryArgs;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::duration::parse_duration;
+use super::duration::time_record;
+
+#[derive(Clone)]
+pub struct SessionSpec {
+    pub k: Option<Arc<str>>,
+    pub ts: Arc<str>,
+    pub gap: f64,
+}
+
+struct Session {
+    idx: usize,
+    start: f64,
+    last: f64,
+    seq: u64,
+    rs: Vec<Record>,
+}
+
+struct State {
+    sessions: HashMap<Record, Session>,
+    counts: HashMap<Record, usize>,
+    // (last, seq) -> key of the open session, so we can find idle sessions
+    // in order
+    idle: BTreeMap<(F64SortDishonorProxy, u64), Record>,
+    seq: u64,
+    max_ts: Option<f64>,
+}
+
+// Sessions are buffered until they end (so their end can go in the bucket),
+// which is when a record for the same key comes more than gap after the
+// session's last or when the stream as a whole has moved more than gap past
+// it.
+pub fn stream(spec: &SessionSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+    let spec = spec.clone();
+    let k = spec.k.clone();
+
+    let close_session = Rc::new(move |key: Record, session: Session, w: &mut FnMut(Entry) -> bool| {
+        let mut bucket = vec![];
+        if let Some(ref k) = k {
+            bucket.push((k.clone(), key));
+        }
+        bucket.push((Arc::from("session_index"), Record::from(session.idx as i64)));
+        bucket.push((Arc::from("session_start"), time_record(session.start)));
+        bucket.push((Arc::from("session_end"), time_record(session.last)));
+        let mut substream = bsw(bucket);
+        for r in session.rs {
+            // Disregard flow since one substream ending does not mean we're
+            // done.
+            substream.write(Entry::Record(r), w);
+        }
+        substream.close(w);
+    });
+    let close_session2 = close_session.clone();
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                sessions: HashMap::new(),
+                counts: HashMap::new(),
+                idle: BTreeMap::new(),
+                seq: 0,
+                max_ts: None,
+            },
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    },
+                    Entry::Record(r) => {
+                        let key = match spec.k {
+                            Some(ref k) => r.get_path(k),
+                            None => Record::null(),
+                        };
+                        let t = r.get_path(&spec.ts).coerce_f64();
+                        let max_ts = s.max_ts.map(|t0| t0.max(t)).unwrap_or(t);
+                        s.max_ts = Some(max_ts);
+
+                        if let Some(session) = s.sessions.remove(&key) {
+                            s.idle.remove(&(F64SortDishonorProxy(session.last), session.seq));
+                            if t - session.last > spec.gap {
+                                close_session(key.clone(), session, w);
+                            }
+                            else {
+                                s.sessions.insert(key.clone(), session);
+                            }
+                        }
+
+                        let seq = s.seq;
+                        s.seq += 1;
+                        let session = match s.sessions.remove(&key) {
+                            Some(mut session) => {
+                                session.start = session.start.min(t);
+                                session.last = session.last.max(t);
+                                session.seq = seq;
+                                session.rs.push(r);
+                                session
+                            },
+                            None => {
+                                let count = s.counts.entry(key.clone()).or_insert(0);
+                                let idx = *count;
+                                *count += 1;
+                                Session {
+                                    idx: idx,
+                                    start: t,
+                                    last: t,
+                                    seq: seq,
+                                    rs: vec![r],
+                                }
+                            },
+                        };
+                        s.idle.insert((F64SortDishonorProxy(session.last), session.seq), key.clone());
+                        s.sessions.insert(key, session);
+
+                        loop {
+                            let (last, seq) = match s.idle.keys().next() {
+                                Some(&(ref last, seq)) => (last.0, seq),
+                                None => break,
+                            };
+                            if max_ts - last <= spec.gap {
+                                break;
+                            }
+                            let key = s.idle.remove(&(F64SortDishonorProxy(last), seq)).unwrap();
+                            let session = s.sessions.remove(&key).unwrap();
+                            close_session(key, session, w);
+                        }
+
+                        return true;
+                    },
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in SessionStream");
+                    },
+                }
+            },
+            move |s, w| {
+                let State { mut sessions, idle, .. } = s;
+                for (_, key) in idle.into_iter() {
+                    let session = sessions.remove(&key).unwrap();
+                    close_session2(key, session, w);
+                }
+            },
+        ),
+    );
+}
+
+pub enum SessionArgs {
+}
+
+impl RegistryArgs for SessionArgs {
+    type Val = SessionSpec;
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn parse(args: &[&str]) -> SessionSpec {
+        assert_eq!(2, args.len());
+        return SessionSpec {
+            k: None,
+            ts: Arc::from(args[0]),
+            gap: parse_duration(args[1]),
+        };
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = SessionArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["session"];
+    }
+
+    fn stream(spec: &SessionSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return stream(spec, bsw);
+    }
+}
@@ -0,0 +1,53 @@
+use record::Record;
+use registry::args::RegistryArgs;
+use std::sync::Arc;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::duration::parse_duration;
+use super::time_window::TimeWindowSpec;
+
+pub enum SlidingArgs {
+}
+
+// "swindow,ts,size,slide[,lateness]"
+impl RegistryArgs for SlidingArgs {
+    type Val = TimeWindowSpec;
+
+    fn argct() -> usize {
+        return 3;
+    }
+
+    fn max_argct() -> usize {
+        return 4;
+    }
+
+    fn parse(args: &[&str]) -> TimeWindowSpec {
+        assert!(args.len() == 3 || args.len() == 4);
+        let spec = TimeWindowSpec {
+            ts: Arc::from(args[0]),
+            size: parse_duration(args[1]),
+            slide: parse_duration(args[2]),
+            lateness: args.get(3).map(|a| parse_duration(a)).unwrap_or(0.0),
+        };
+        assert!(spec.size > 0.0, "swindow needs a positive size");
+        assert!(spec.slide > 0.0, "swindow needs a positive slide");
+        return spec;
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = SlidingArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["swindow"];
+    }
+
+    fn stream(spec: &TimeWindowSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return super::time_window::stream(spec, bsw);
+    }
+}
@@ -0,0 +1,141 @@
+use record::Record;
+use record::RecordTrait;
+use registry::args::RegistryArgs;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::ClumperBe;
+use super::ClumperRegistrant;
+use super::duration::parse_duration;
+use super::duration::time_record;
+
+#[derive(Clone)]
+pub struct TimeWindowSpec {
+    pub ts: Arc<str>,
+    pub size: f64,
+    pub slide: f64,
+    pub lateness: f64,
+}
+
+// Windows are [k * slide, k * slide + size) for integer k, so a record is in
+// every window whose start is in (ts - size, ts].  Windows are closed once the
+// largest timestamp seen passes their end by more than the allowed lateness,
+// and records arriving for windows already closed are dropped.
+pub fn stream(spec: &TimeWindowSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+    struct State {
+        windows: BTreeMap<i64, Stream>,
+        max_ts: Option<f64>,
+    }
+
+    let spec = spec.clone();
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                windows: BTreeMap::new(),
+                max_ts: None,
+            },
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    },
+                    Entry::Record(r) => {
+                        let t = r.get_path(&spec.ts).coerce_f64();
+                        let max_ts = s.max_ts.map(|t0| t0.max(t)).unwrap_or(t);
+                        s.max_ts = Some(max_ts);
+                        let watermark = max_ts - spec.lateness;
+
+                        let k_min = ((t - spec.size) / spec.slide).floor() as i64 + 1;
+                        let k_max = (t / spec.slide).floor() as i64;
+                        for k in k_min..=k_max {
+                            let start = (k as f64) * spec.slide;
+                            let end = start + spec.size;
+                            if end <= watermark {
+                                // too late
+                                continue;
+                            }
+                            let substream = s.windows.entry(k).or_insert_with(|| {
+                                return bsw(vec![
+                                    (Arc::from("window_start"), time_record(start)),
+                                    (Arc::from("window_end"), time_record(end)),
+                                ]);
+                            });
+
+                            // Disregard flow since one substream ending does
+                            // not mean we're done.
+                            substream.write(Entry::Record(r.clone()), w);
+                        }
+
+                        loop {
+                            let k = match s.windows.keys().next() {
+                                Some(&k) => k,
+                                None => break,
+                            };
+                            if (k as f64) * spec.slide + spec.size > watermark {
+                                break;
+                            }
+                            s.windows.remove(&k).unwrap().close(w);
+                        }
+
+                        return true;
+                    },
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in TimeWindowStream");
+                    },
+                }
+            },
+            |s, w| {
+                for (_, substream) in s.windows.into_iter() {
+                    substream.close(w);
+                }
+            },
+        ),
+    );
+}
+
+pub enum TumblingArgs {
+}
+
+// "twindow,ts,size[,lateness]"
+impl RegistryArgs for TumblingArgs {
+    type Val = TimeWindowSpec;
+
+    fn argct() -> usize {
+        return 2;
+    }
+
+    fn max_argct() -> usize {
+        return 3;
+    }
+
+    fn parse(args: &[&str]) -> TimeWindowSpec {
+        assert!(args.len() == 2 || args.len() == 3);
+        let size = parse_duration(args[1]);
+        assert!(size > 0.0, "twindow needs a positive size");
+        return TimeWindowSpec {
+            ts: Arc::from(args[0]),
+            size: size,
+            slide: size,
+            lateness: args.get(2).map(|a| parse_duration(a)).unwrap_or(0.0),
+        };
+    }
+}
+
+pub type Impl = ClumperRegistrant<ImplBe>;
+
+pub struct ImplBe();
+
+impl ClumperBe for ImplBe {
+    type Args = TumblingArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["twindow"];
+    }
+
+    fn stream(spec: &TimeWindowSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        return stream(spec, bsw);
+    }
+}
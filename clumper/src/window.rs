@@ -1,5 +1,5 @@
 use record::Record;
-use registry::args::OneUsizeArgs;
+use registry::args::RegistryArgs;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use stream::Entry;
@@ -7,46 +7,155 @@ use stream::Stream;
 use super::ClumperBe;
 use super::ClumperRegistrant;
 
+#[derive(Clone)]
+pub struct WindowSpec {
+    size: usize,
+    step: usize,
+    partial: bool,
+    keep_bof: bool,
+    pos: bool,
+}
+
+pub enum WindowArgs {
+}
+
+// window,<size>[,<step>[,<flag>...]] where flags are "partial" to also emit
+// the trailing windows that never fill up, "keep-bof" to not start over at
+// the beginning of each file and "pos" to put the window's index and record
+// positions in the bucket.
+impl RegistryArgs for WindowArgs {
+    type Val = WindowSpec;
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn max_argct() -> usize {
+        return 5;
+    }
+
+    fn parse(args: &[&str]) -> WindowSpec {
+        let mut spec = WindowSpec {
+            size: args[0].parse().unwrap(),
+            step: 1,
+            partial: false,
+            keep_bof: false,
+            pos: false,
+        };
+        if args.len() > 1 {
+            spec.step = args[1].parse().unwrap();
+        }
+        for flag in args.iter().skip(2) {
+            match *flag {
+                "partial" => spec.partial = true,
+                "keep-bof" | "keepbof" => spec.keep_bof = true,
+                "pos" => spec.pos = true,
+                _ => panic!("Unknown window flag {}", flag),
+            }
+        }
+        assert!(spec.size > 0, "window needs a positive size");
+        assert!(spec.step > 0, "window needs a positive step");
+        return spec;
+    }
+}
+
+struct State {
+    rs: VecDeque<Record>,
+    // records seen since last reset
+    pos: usize,
+    // windows emitted since last reset
+    idx: usize,
+}
+
+impl State {
+    fn reset(&mut self) {
+        self.rs.clear();
+        self.pos = 0;
+        self.idx = 0;
+    }
+
+    // Emits the window of records [start, end) which must still be in rs.
+    fn emit(&mut self, spec: &WindowSpec, bsw: &Fn(Vec<(Arc<str>, Record)>) -> Stream, start: usize, end: usize, w: &mut FnMut(Entry) -> bool) {
+        let mut bucket = vec![];
+        if spec.pos {
+            bucket.push((Arc::from("window_index"), Record::from(self.idx as i64)));
+            bucket.push((Arc::from("window_pos_start"), Record::from(start as i64)));
+            bucket.push((Arc::from("window_pos_end"), Record::from(end as i64)));
+        }
+        let mut substream = bsw(bucket);
+        self.idx += 1;
+
+        let offset = self.pos - self.rs.len();
+        for r in self.rs.iter().skip(start - offset).take(end - start) {
+            // Disregard flow since one substream ending does not mean we're
+            // done (e.g.  each substream could be head -n 1).
+            substream.write(Entry::Record(r.clone()), w);
+        }
+
+        substream.close(w);
+    }
+
+    // Windows that started (on a step) but will never fill up.
+    fn emit_partials(&mut self, spec: &WindowSpec, bsw: &Fn(Vec<(Arc<str>, Record)>) -> Stream, w: &mut FnMut(Entry) -> bool) {
+        let first = (self.pos + 1).saturating_sub(spec.size);
+        let first = (first + spec.step - 1) / spec.step * spec.step;
+        let pos = self.pos;
+        for start in (first..pos).step_by(spec.step) {
+            if start + spec.size > pos {
+                self.emit(spec, bsw, start, pos, w);
+            }
+        }
+    }
+}
+
 pub type Impl = ClumperRegistrant<ImplBe>;
 
 pub struct ImplBe();
 
 impl ClumperBe for ImplBe {
-    type Args = OneUsizeArgs;
+    type Args = WindowArgs;
 
     fn names() -> Vec<&'static str> {
         return vec!["window"];
     }
 
-    fn stream(size: &usize, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
-        let size = *size;
+    fn stream(spec: &WindowSpec, bsw: Box<Fn(Vec<(Arc<str>, Record)>) -> Stream>) -> Stream {
+        let spec = Arc::new(spec.clone());
+        let bsw: Arc<Fn(Vec<(Arc<str>, Record)>) -> Stream> = Arc::from(bsw);
+        let spec2 = spec.clone();
+        let bsw2 = bsw.clone();
 
         return stream::compound(
             stream::parse(),
             stream::closures(
-                VecDeque::new(),
+                State {
+                    rs: VecDeque::new(),
+                    pos: 0,
+                    idx: 0,
+                },
                 move |s, e, w| {
                     match e {
                         Entry::Bof(file) => {
-                            s.clear();
+                            if !spec.keep_bof {
+                                if spec.partial {
+                                    s.emit_partials(&spec, &*bsw, w);
+                                }
+                                s.reset();
+                            }
                             return w(Entry::Bof(file));
                         },
                         Entry::Record(r) => {
-                            s.push_back(r);
-                            if s.len() > size {
-                                s.pop_front();
+                            s.rs.push_back(r);
+                            s.pos += 1;
+                            if s.rs.len() > spec.size {
+                                s.rs.pop_front();
                             }
-                            if s.len() == size {
-                                let mut substream = bsw(vec![]);
-
-                                for r in s {
-                                    // Disregard flow since one substream
-                                    // ending does not mean we're done (e.g.
-                                    // each substream could be head -n 1).
-                                    substream.write(Entry::Record(r.clone()), w);
+                            if s.rs.len() == spec.size {
+                                let start = s.pos - spec.size;
+                                if start % spec.step == 0 {
+                                    let end = s.pos;
+                                    s.emit(&spec, &*bsw, start, end, w);
                                 }
-
-                                substream.close(w);
                             }
                             return true;
                         },
@@ -55,7 +164,10 @@ impl ClumperBe for ImplBe {
                         },
                     }
                 },
-                |_s, _w| {
+                move |mut s, w| {
+                    if spec2.partial {
+                        s.emit_partials(&spec2, &*bsw2, w);
+                    }
                 },
             ),
         );
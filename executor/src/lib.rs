@@ -11,6 +11,8 @@ extern crate rlua;
 use record::Record;
 use registry::Registrant;
 use registry::args::ZeroArgs;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 pub type BoxedExecutor = Box<ExecutorInbox>;
 pub type BoxedExecutor2 = Box<Executor2Inbox>;
@@ -26,7 +28,7 @@ pub trait ExecutorInbox {
 }
 
 pub trait Executor2Inbox: Send + Sync {
-    fn stream(&self, ret: bool) -> Box<FnMut(Record) -> Record>;
+    fn stream(&self, ret: bool) -> Box<FnMut(Record) -> Record + Send>;
     fn box_clone(&self) -> BoxedExecutor2;
 }
 
@@ -41,7 +43,7 @@ pub trait ExecutorBe {
 
     fn names() -> Vec<&'static str>;
     fn parse(code: &str) -> Self::Code;
-    fn stream(code: &Self::Code, ret: bool) -> Box<FnMut(Record) -> Record>;
+    fn stream(code: &Self::Code, ret: bool) -> Box<FnMut(Record) -> Record + Send>;
 }
 
 pub struct ExecutorRegistrant<B: ExecutorBe> {
@@ -79,7 +81,7 @@ impl<B: ExecutorBe + 'static> ExecutorInbox for ExecutorInboxImpl<B> {
 }
 
 impl<B: ExecutorBe + 'static> Executor2Inbox for Executor2InboxImpl<B> {
-    fn stream(&self, ret: bool) -> Box<FnMut(Record) -> Record> {
+    fn stream(&self, ret: bool) -> Box<FnMut(Record) -> Record + Send> {
         return <B as ExecutorBe>::stream(&self.code, ret);
     }
 
@@ -89,3 +91,49 @@ impl<B: ExecutorBe + 'static> Executor2Inbox for Executor2InboxImpl<B> {
         });
     }
 }
+
+// For code given inline (e.g.  as a registry argument) where there's no room
+// for an --engine option.  An "<engine>:" prefix picks the engine, otherwise
+// it's r4l.
+pub fn parse_inline(code: &str) -> BoxedExecutor2 {
+    if let Some(i) = code.find(':') {
+        let engine = &code[0..i];
+        if !engine.is_empty() && engine.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return REGISTRY.find(engine, &[]).parse(&code[(i + 1)..]);
+        }
+    }
+    return REGISTRY.find(r4l::Impl::names()[0], &[]).parse(code);
+}
+
+// A single stream of some code, shared between (and callable from) anything
+// that needs to be Send + Sync + Clone, e.g.  sorts.  Use fresh() for a copy
+// with its own state (e.g.  one per aggregator bucket).
+#[derive(Clone)]
+pub struct SharedStream {
+    code: BoxedExecutor2,
+    ret: bool,
+    f: Arc<Mutex<Box<FnMut(Record) -> Record + Send>>>,
+}
+
+impl SharedStream {
+    pub fn new(code: &BoxedExecutor2, ret: bool) -> Self {
+        return SharedStream {
+            code: code.clone(),
+            ret: ret,
+            f: Arc::new(Mutex::new(code.stream(ret))),
+        };
+    }
+
+    pub fn parse_inline(code: &str, ret: bool) -> Self {
+        return SharedStream::new(&parse_inline(code), ret);
+    }
+
+    pub fn fresh(&self) -> Self {
+        return SharedStream::new(&self.code, self.ret);
+    }
+
+    pub fn call(&self, r: Record) -> Record {
+        let mut f = self.f.lock().unwrap();
+        return (&mut *f)(r);
+    }
+}
@@ -136,7 +136,7 @@ impl ExecutorBe for ImplBe {
         return code.to_string();
     }
 
-    fn stream(code: &String, ret: bool) -> Box<FnMut(Record) -> Record> {
+    fn stream(code: &String, ret: bool) -> Box<FnMut(Record) -> Record + Send> {
         let lua = Lua::new();
 
         // Our library of functions to help manage API "issues".
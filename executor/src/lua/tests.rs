@@ -1,6 +1,7 @@
 use record::Record;
 use registry::Registrant;
 use super::Impl;
+use SharedStream;
 
 fn test_one(i: &str, c: &str, o: &str) {
     let r = Record::parse(i);
@@ -52,3 +53,11 @@ fn test_to_lua() {
 fn test_arr() {
     test_one(r#"{}"#, r#"r["a"] = arr({1, "b"})"#, r#"{"a":[1,"b"]}"#);
 }
+
+#[test]
+fn test_shared_stream_fresh() {
+    let s = SharedStream::new(&Impl::init2(()).parse(r#"n = (n or 0) + 1; r["n"] = n"#), false);
+    assert_eq!(s.call(Record::parse("{}")).deparse(), r#"{"n":1}"#);
+    assert_eq!(s.clone().call(Record::parse("{}")).deparse(), r#"{"n":2}"#);
+    assert_eq!(s.fresh().call(Record::parse("{}")).deparse(), r#"{"n":1}"#);
+}
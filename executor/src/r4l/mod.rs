@@ -204,7 +204,7 @@ impl ExecutorBe for ImplBe {
         return Code(Arc::new(parse::StatementParser::new().parse(code).unwrap()));
     }
 
-    fn stream(code: &Code, ret: bool) -> Box<FnMut(Record) -> Record> {
+    fn stream(code: &Code, ret: bool) -> Box<FnMut(Record) -> Record + Send> {
         let e = code.0.clone();
         let mut st = State::default();
         return Box::new(move |r| {
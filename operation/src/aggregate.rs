@@ -4,9 +4,15 @@ use opts::vals::BooleanOption;
 use opts::vals::UnvalidatedOption;
 use record::Record;
 use record::RecordTrait;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::Arc;
 use stream::Entry;
 use stream::Stream;
+use super::Bucket;
+use super::CheckpointOptions;
+use super::Checkpointable;
+use super::Checkpointer;
 use super::OperationBe2;
 use super::OperationBeForBe2;
 use super::OperationRegistrant;
@@ -20,6 +26,7 @@ pub struct Options {
     tru: TwoRecordUnionOption,
     incremental: BooleanOption,
     no_bucket: BooleanOption,
+    pub(crate) cp: CheckpointOptions,
 }
 
 pub(crate) type Impl = OperationRegistrant<ImplBe>;
@@ -43,77 +50,116 @@ impl OperationBe2 for ImplBe2 {
         opt.sub(|p| &mut p.incremental).match_zero(&["no-incremental"], BooleanOption::clear);
         opt.sub(|p| &mut p.no_bucket).match_zero(&["bucket"], BooleanOption::clear);
         opt.sub(|p| &mut p.no_bucket).match_zero(&["no-bucket"], BooleanOption::set);
+        CheckpointOptions::options(&mut opt.sub(|p| &mut p.cp));
     }
 
     fn stream(o: Arc<OptionsValidated>) -> Stream {
-        struct State {
-            o: Arc<OptionsValidated>,
-            aggs: Vec<(String, BoxedAggregator)>,
-            recs: Vec<Record>,
-        }
-        fn aggregate_record(aggs: Vec<(String, BoxedAggregator)>) -> Record {
-            let mut rhs = Record::empty_hash();
-            for (label, state) in aggs.clone().into_iter() {
-                rhs.set_path(&label, state.finish());
-            }
-            return rhs;
+        let cp = Checkpointer::new(&o.cp);
+        let s = bucket_stream(o.clone(), vec![], &cp);
+        return cp.clone().wrap(s, move |bucket| bucket_stream(o.clone(), bucket, &cp));
+    }
+}
+
+struct State {
+    o: Arc<OptionsValidated>,
+    aggs: Vec<(String, BoxedAggregator)>,
+    recs: Vec<Record>,
+}
+
+impl Checkpointable for State {
+    fn save(&self) -> Record {
+        let mut r = Record::empty_hash();
+        r.set_path("aggs", Record::from_hash(self.aggs.iter().map(|(label, state)| (Arc::from(&label as &str), state.save())).collect()));
+        r.set_path("recs", Record::from_vec(self.recs.clone()));
+        return r;
+    }
+}
+
+impl State {
+    fn load(&mut self, r: &Record) {
+        let aggs = r.get_path("aggs");
+        let aggs = aggs.expect_hash();
+        for (label, state) in self.aggs.iter_mut() {
+            state.load(&aggs[&label as &str]);
         }
+        self.recs = r.get_path("recs").expect_array().clone();
+    }
+}
 
-        return stream::compound(
-            stream::parse(),
-            stream::closures(
-                State {
-                    o: o.clone(),
-                    aggs: o.aggs.clone(),
-                    recs: Vec::new(),
-                },
-                |s, e, w| {
-                    match e {
-                        Entry::Bof(_file) => {
-                            return true;
-                        }
-                        Entry::Record(r) => {
-                            for (_, ref mut state) in s.aggs.iter_mut() {
-                                state.add(r.clone());
-                            }
+fn aggregate_record(aggs: Vec<(String, BoxedAggregator)>) -> Record {
+    let mut rhs = Record::empty_hash();
+    for (label, state) in aggs.clone().into_iter() {
+        rhs.set_path(&label, state.finish());
+    }
+    return rhs;
+}
 
-                            if s.o.incremental {
-                                if s.o.no_bucket {
-                                    return w(Entry::Record(s.o.tru.union(r, aggregate_record(s.aggs.clone()))));
-                                }
+// The aggregation for one bucket (all of input for aggregate itself), picking
+// up where a resumed checkpoint left off, if anywhere.
+pub(crate) fn bucket_stream(o: Arc<OptionsValidated>, bucket: Bucket, cp: &Rc<Checkpointer>) -> Stream {
+    let s = Rc::new(RefCell::new(State {
+        o: o.clone(),
+        aggs: o.aggs.clone(),
+        recs: Vec::new(),
+    }));
+    let c: Rc<RefCell<Checkpointable>> = s.clone();
+    if let Some(r) = cp.register(&bucket, Rc::downgrade(&c)) {
+        s.borrow_mut().load(&r);
+    }
 
-                                return w(Entry::Record(s.o.tru.union_maybe(None, Some(aggregate_record(s.aggs.clone())))));
-                            }
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            s,
+            |s, e, w| {
+                let mut s = s.borrow_mut();
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    }
+                    Entry::Record(r) => {
+                        for (_, ref mut state) in s.aggs.iter_mut() {
+                            state.add(r.clone());
+                        }
 
+                        if s.o.incremental {
                             if s.o.no_bucket {
-                                s.recs.push(r);
+                                return w(Entry::Record(s.o.tru.union(r, aggregate_record(s.aggs.clone()))));
                             }
-                            return true;
+
+                            return w(Entry::Record(s.o.tru.union_maybe(None, Some(aggregate_record(s.aggs.clone())))));
                         }
-                        Entry::Line(_line) => {
-                            panic!("Unexpected line in AggregateStream");
+
+                        if s.o.no_bucket {
+                            s.recs.push(r);
                         }
+                        return true;
                     }
-                },
-                |s, w| {
-                    if s.o.incremental {
-                        return;
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in AggregateStream");
                     }
+                }
+            },
+            |s, w| {
+                let s = s.borrow();
 
-                    let rhs = aggregate_record(s.aggs);
+                if s.o.incremental {
+                    return;
+                }
 
-                    if !s.o.no_bucket {
-                        w(Entry::Record(s.o.tru.union_maybe(None, Some(rhs))));
-                        return;
-                    }
+                let rhs = aggregate_record(s.aggs.clone());
 
-                    for lhs in s.recs {
-                        if !w(Entry::Record(s.o.tru.union(lhs, rhs.clone()))) {
-                            return;
-                        }
+                if !s.o.no_bucket {
+                    w(Entry::Record(s.o.tru.union_maybe(None, Some(rhs))));
+                    return;
+                }
+
+                for lhs in s.recs.iter() {
+                    if !w(Entry::Record(s.o.tru.union(lhs.clone(), rhs.clone()))) {
+                        return;
                     }
-                },
-            ),
-        );
-    }
+                }
+            },
+        ),
+    );
 }
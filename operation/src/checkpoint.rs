@@ -0,0 +1,257 @@
+use opts::parser::OptParserView;
+use opts::vals::DefaultedOption;
+use opts::vals::OptionalStringOption;
+use record::Record;
+use record::RecordTrait;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::rc::Rc;
+use std::rc::Weak;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use validates::Validates;
+
+option_defaulters! {
+    EveryDefaulter: usize => 100000,
+}
+
+#[derive(Default)]
+#[derive(Validates)]
+pub struct CheckpointOptions {
+    file: OptionalStringOption,
+    every: DefaultedOption<usize, EveryDefaulter>,
+    resume: OptionalStringOption,
+}
+
+impl CheckpointOptions {
+    pub fn options<'a>(opt: &mut OptParserView<'a, CheckpointOptions>) {
+        opt.sub(|p| &mut p.file).match_single(&["checkpoint"], OptionalStringOption::set_str);
+        opt.match_single(&["checkpoint-every"], |p, a| p.every.set(a.parse().unwrap()));
+        opt.sub(|p| &mut p.resume).match_single(&["resume"], OptionalStringOption::set_str);
+    }
+}
+
+pub(crate) type Bucket = Vec<(Arc<str>, Record)>;
+
+pub(crate) trait Checkpointable {
+    fn save(&self) -> Record;
+}
+
+// Where we are in the input, by Entry::Bof names and a count of entries since
+// the last one.
+#[derive(Default)]
+struct Position {
+    done: Vec<Arc<str>>,
+    file: Option<Arc<str>>,
+    entries: usize,
+}
+
+pub(crate) struct Checkpointer {
+    file: Option<String>,
+    every: usize,
+    live: RefCell<Vec<(Bucket, Weak<RefCell<Checkpointable>>)>>,
+    restored: RefCell<Restored>,
+    restored_pos: Position,
+}
+
+// Bucket states from the checkpoint being resumed from that haven't been
+// handed back out yet, kept in checkpoint file order.
+#[derive(Default)]
+struct Restored {
+    order: Vec<Bucket>,
+    states: HashMap<Bucket, VecDeque<Record>>,
+}
+
+impl Restored {
+    fn push(&mut self, bucket: Bucket, state: Record) {
+        let order = &mut self.order;
+        self.states.entry(bucket.clone()).or_insert_with(|| {
+            order.push(bucket);
+            return VecDeque::new();
+        }).push_back(state);
+    }
+
+    fn take(&mut self, bucket: &Bucket) -> Option<Record> {
+        let ret = match self.states.get_mut(bucket) {
+            Some(states) => states.pop_front(),
+            None => None,
+        };
+        if self.states.get(bucket).map(|states| states.is_empty()).unwrap_or(false) {
+            self.states.remove(bucket);
+            self.order.retain(|b| b != bucket);
+        }
+        return ret;
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&Bucket, &Record)> {
+        let states = &self.states;
+        return self.order.iter().flat_map(move |bucket| states[bucket].iter().map(move |state| (bucket, state)));
+    }
+}
+
+impl Checkpointer {
+    pub fn new(o: &CheckpointOptionsValidated) -> Rc<Checkpointer> {
+        let mut restored = Restored::default();
+        let mut restored_pos = Position::default();
+        if let Some(resume) = &o.resume {
+            let mut lines = BufReader::new(File::open(resume).unwrap()).lines();
+            let pos = Record::parse(&lines.next().unwrap().unwrap());
+            restored_pos.done = pos.get_path("done").expect_array().iter().map(|f| f.expect_string()).collect();
+            restored_pos.file = match pos.get_path("file").maybe_primitive() {
+                Some(record::JsonPrimitive::Null()) => None,
+                _ => Some(pos.get_path("file").expect_string()),
+            };
+            restored_pos.entries = pos.get_path("entries").coerce_num().map_right(|f| f as i64).join() as usize;
+            for line in lines {
+                let r = Record::parse(&line.unwrap());
+                let bucket = r.get_path("bucket").expect_array().iter().map(|pair| {
+                    let pair = pair.expect_array();
+                    return (pair[0].expect_string(), pair[1].clone());
+                }).collect();
+                restored.push(bucket, r.get_path("state"));
+            }
+        }
+
+        return Rc::new(Checkpointer {
+            file: o.file.clone(),
+            every: o.every,
+            live: RefCell::new(Vec::new()),
+            restored: RefCell::new(restored),
+            restored_pos: restored_pos,
+        });
+    }
+
+    // Hand back one restored state for a bucket being opened (if any) and
+    // start including the bucket in checkpoints.
+    pub fn register(&self, bucket: &Bucket, c: Weak<RefCell<Checkpointable>>) -> Option<Record> {
+        self.live.borrow_mut().push((bucket.clone(), c));
+
+        return self.restored.borrow_mut().take(bucket);
+    }
+
+    fn write(&self, pos: &Position) {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return,
+        };
+
+        let mut lines = Vec::new();
+
+        let mut r = Record::empty_hash();
+        r.set_path("done", Record::from_vec(pos.done.iter().map(|f| Record::from(f.clone())).collect()));
+        r.set_path("file", pos.file.clone().map(Record::from).unwrap_or_else(Record::null));
+        r.set_path("entries", Record::from(pos.entries as i64));
+        lines.push(r);
+
+        let mut bucket_line = |bucket: &Bucket, state: Record| {
+            let mut r = Record::empty_hash();
+            r.set_path("bucket", Record::from_vec(bucket.iter().map(|(k, v)| Record::from_vec(vec![Record::from(k.clone()), v.clone()])).collect()));
+            r.set_path("state", state);
+            lines.push(r);
+        };
+
+        let mut live = self.live.borrow_mut();
+        live.retain(|(_bucket, c)| c.upgrade().is_some());
+        for (bucket, c) in live.iter() {
+            bucket_line(bucket, c.upgrade().unwrap().borrow().save());
+        }
+        // Anything restored but not yet reopened must survive into the next
+        // checkpoint as well.
+        for (bucket, state) in self.restored.borrow().iter() {
+            bucket_line(bucket, state.clone());
+        }
+
+        let tmp = format!("{}.tmp", file);
+        {
+            let mut f = File::create(&tmp).unwrap();
+            for r in lines {
+                writeln!(f, "{}", r.deparse()).unwrap();
+            }
+        }
+        std::fs::rename(&tmp, file).unwrap();
+    }
+
+    // Wrap the top of an aggregate/collate: skip input consumed before the
+    // checkpoint being resumed from, write checkpoints every so often, and
+    // at close reopen (via bsw) any restored bucket the input never got back
+    // around to so it still produces output.  Those come out first, in
+    // checkpoint order, since they were opened before anything since the
+    // resume.
+    //
+    // Note only bucket states are saved, so state held by the clumpers
+    // themselves (e.g.  a partially filled window) starts over on resume.
+    pub fn wrap<F: Fn(Bucket) -> Stream + 'static>(self: Rc<Self>, s: Stream, bsw: F) -> Stream {
+        struct State {
+            cp: Rc<Checkpointer>,
+            s: Stream,
+            pos: Position,
+            skip_files: HashSet<Arc<str>>,
+            skip: usize,
+            since: usize,
+        }
+
+        let mut skip_files = HashSet::new();
+        for f in self.restored_pos.done.iter() {
+            skip_files.insert(f.clone());
+        }
+        let skip = if self.restored_pos.file.is_none() { self.restored_pos.entries } else { 0 };
+
+        return stream::closures(
+            State {
+                cp: self,
+                s: s,
+                pos: Position::default(),
+                skip_files: skip_files,
+                skip: skip,
+                since: 0,
+            },
+            |s, e, w| {
+                match e {
+                    Entry::Bof(file) => {
+                        if let Some(prev) = s.pos.file.take() {
+                            s.pos.done.push(prev);
+                        }
+                        s.pos.file = Some(file.clone());
+                        s.pos.entries = 0;
+                        s.skip = 0;
+                        if s.cp.restored_pos.file.as_ref() == Some(&file) {
+                            s.skip = s.cp.restored_pos.entries;
+                        }
+                        if s.skip_files.contains(&file) {
+                            s.skip = std::usize::MAX;
+                        }
+                        return s.s.write(Entry::Bof(file), w);
+                    }
+                    e => {
+                        s.pos.entries += 1;
+                        if s.pos.entries <= s.skip {
+                            return true;
+                        }
+                        let ret = s.s.write(e, w);
+                        s.since += 1;
+                        if s.since >= s.cp.every {
+                            s.cp.write(&s.pos);
+                            s.since = 0;
+                        }
+                        return ret;
+                    }
+                }
+            },
+            move |s, w| {
+                let restored: Vec<_> = s.cp.restored.borrow().iter().map(|(bucket, _state)| bucket.clone()).collect();
+                for bucket in restored {
+                    bsw(bucket).close(w);
+                }
+
+                s.s.close(w);
+            },
+        );
+    }
+}
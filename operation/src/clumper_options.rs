@@ -21,6 +21,11 @@ impl ClumperOptions {
                 (p.0).0.push(clumper::key::Impl::init(&[a]));
             }
         });
+        opt.match_n(&["kn", "key-norm"], 2, |p, a| {
+            for k in a[1].split(',') {
+                (p.0).0.push(clumper::key_norm::Impl::init(&[k, &a[0]]));
+            }
+        });
     }
 }
 
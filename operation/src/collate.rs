@@ -1,7 +1,10 @@
 use opts::parser::OptParserView;
 use opts::vals::IntoArcOption;
+use std::rc::Rc;
 use std::sync::Arc;
 use stream::Stream;
+use super::Bucket;
+use super::Checkpointer;
 use super::ClumperOptions;
 use super::OperationBe;
 use super::OperationRegistrant;
@@ -36,15 +39,20 @@ impl OperationBe for ImplBe {
     }
 
     fn stream(o: Arc<OptionsValidated>) -> Stream {
+        let cp = Checkpointer::new(&o.ag.p.cp);
         let o2 = o.clone();
-        return o.cl.stream(move |bucket| {
-            let s = stream::transform_records(move |mut r| {
+        let cp2 = cp.clone();
+        let bsw = Rc::new(move |bucket: Bucket| {
+            let s = aggregate::bucket_stream(o2.ag.p.clone(), bucket.clone(), &cp2);
+            return stream::compound(s, stream::transform_records(move |mut r| {
                 for (path, v) in &bucket {
                     r.set_path(&path, v.clone());
                 }
                 return r;
-            });
-            return stream::compound(aggregate::ImplBe::stream(o2.ag.clone()), s);
+            }));
         });
+        let bsw2 = bsw.clone();
+        let s = o.cl.stream(move |bucket| bsw(bucket));
+        return cp.wrap(s, move |bucket| bsw2(bucket));
     }
 }
@@ -1,64 +1,467 @@
 use opts::parser::OptParserView;
-use opts::vals::RequiredStringOption;
+use opts::vals::BooleanOption;
+use opts::vals::OptionalOption;
+use opts::vals::StringVecOption;
 use opts::vals::UnvalidatedOption;
+use record::KeyNorm;
 use record::Record;
+use record::RecordTrait;
+use registry::Registrant;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Lines;
+use std::ops::Range;
 use std::sync::Arc;
 use stream::Entry;
 use stream::Stream;
-use super::OperationBe2;
-use super::OperationBeForBe2;
+use super::OperationBe;
 use super::OperationRegistrant;
+use super::SortComparator;
+use super::SortOptions;
+use super::SortOptionsValidated;
+use super::SubOperationOption;
+use super::SubOperationOptionValidated;
 use super::TwoRecordUnionOption;
 use validates::Validates;
 
 #[derive(Default)]
 struct DbOption {
     pairs: UnvalidatedOption<Vec<(String, String)>>,
-    file: RequiredStringOption,
+    norm: UnvalidatedOption<KeyNorm>,
+    files: StringVecOption,
+    op_mode: UnvalidatedOption<bool>,
+    op: SubOperationOption,
+    args: StringVecOption,
+    range: OptionalOption<RangeSpec>,
+    merge: BooleanOption,
+    sorts: SortOptions,
+}
+
+impl DbOption {
+    // The first bare arg is the db file unless given by --db (or after
+    // --db-op, which takes the rest of the args as the db sub-operation).
+    fn maybe_push(&mut self, a: &str) -> bool {
+        if self.op_mode.0 {
+            return false;
+        }
+        if self.files.0.is_empty() {
+            self.files.push(a);
+            return true;
+        }
+        self.args.push(a);
+        return true;
+    }
+
+    fn push_glob(&mut self, a: &str) {
+        let mut paths: Vec<_> = glob::glob(a).unwrap().map(|p| p.unwrap().to_string_lossy().into_owned()).collect();
+        if paths.is_empty() {
+            panic!("No db files match {}", a);
+        }
+        self.files.0.append(&mut paths);
+    }
 }
 
 impl Validates for DbOption {
-    type Target = Db;
+    type Target = DbOptionValidated;
+
+    fn validate(self) -> DbOptionValidated {
+        let pairs = self.pairs.validate();
+        let norm = self.norm.validate();
+        let range = self.range.validate();
+        let (op, extra) = match self.op_mode.validate() {
+            true => {
+                let op = self.op.validate();
+                let extra = op.extra.clone();
+                (Some(op), extra)
+            }
+            false => (None, self.args.validate()),
+        };
+        let source = DbSource {
+            files: self.files.validate(),
+            op: op,
+        };
+        assert!(!source.files.is_empty(), "join needs a db file");
+
+        if !self.merge.validate() {
+            assert!(self.sorts.is_empty(), "join -s only works with --merge");
+            return DbOptionValidated {
+                mode: DbMode::Hash(Db::new(source.open(), &pairs, norm, range)),
+                extra: extra,
+            };
+        }
+
+        assert!(range.is_none(), "join --merge doesn't do range joins");
+        let mut sorts = self.sorts;
+        if sorts.is_empty() {
+            for (_lk, rk) in pairs.iter() {
+                sorts.push(sorts::lexical::Impl::init(&[rk]));
+            }
+        }
+        return DbOptionValidated {
+            mode: DbMode::Merge(MergeSpec {
+                source: source,
+                pairs: Arc::new(pairs),
+                norm: norm,
+                sorts: Arc::new(sorts.validate()),
+            }),
+            extra: extra,
+        };
+    }
+}
+
+#[derive(Clone)]
+struct DbOptionValidated {
+    mode: DbMode,
+    extra: Vec<String>,
+}
+
+#[derive(Clone)]
+enum DbMode {
+    Hash(Db),
+    Merge(MergeSpec),
+}
+
+#[derive(Clone)]
+struct DbSource {
+    files: Vec<String>,
+    op: Option<SubOperationOptionValidated>,
+}
+
+impl DbSource {
+    fn open(&self) -> DbReader {
+        return DbReader {
+            files: self.files.iter().cloned().collect(),
+            lines: None,
+            op: self.op.as_ref().map(|op| op.wr.stream()),
+            buf: VecDeque::new(),
+        };
+    }
+}
+
+// Pulls db records from the files in turn, either as JSON lines or through
+// the --db-op sub-operation.
+struct DbReader {
+    files: VecDeque<String>,
+    lines: Option<Lines<BufReader<File>>>,
+    op: Option<Stream>,
+    buf: VecDeque<Record>,
+}
+
+fn buffer(buf: &mut VecDeque<Record>, e: Entry) -> bool {
+    match e {
+        Entry::Bof(_file) => {
+        }
+        Entry::Record(r) => {
+            buf.push_back(r);
+        }
+        Entry::Line(line) => {
+            buf.push_back(Record::parse(&line));
+        }
+    }
+    return true;
+}
+
+impl DbReader {
+    fn write(&mut self, e: Entry) {
+        let buf = &mut self.buf;
+        match self.op {
+            Some(ref mut op) => {
+                let more = op.write(e, &mut |e| buffer(buf, e));
+                if !more {
+                    self.files.clear();
+                    self.lines = None;
+                }
+            }
+            None => {
+                if let Entry::Line(line) = e {
+                    buf.push_back(Record::parse(&line));
+                }
+            }
+        }
+    }
+}
 
-    fn validate(self) -> Db {
-        return Db::new(&self.file.validate(), &self.pairs.validate());
+impl Iterator for DbReader {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            if let Some(r) = self.buf.pop_front() {
+                return Some(r);
+            }
+            if let Some(line) = self.lines.as_mut().and_then(|lines| lines.next()) {
+                self.write(Entry::Line(Arc::from(line.unwrap())));
+                continue;
+            }
+            if let Some(file) = self.files.pop_front() {
+                self.lines = Some(BufReader::new(File::open(&file).unwrap()).lines());
+                self.write(Entry::Bof(Arc::from(file)));
+                continue;
+            }
+            match self.op.take() {
+                Some(op) => {
+                    let buf = &mut self.buf;
+                    op.close(&mut |e| buffer(buf, e));
+                }
+                None => {
+                    return None;
+                }
+            }
+        }
     }
 }
 
 #[derive(Clone)]
 struct Db {
-    db: HashMap<Vec<Record>, (bool, Vec<Record>)>,
+    db: HashMap<Vec<Record>, Vec<DbEntry>>,
+    unmatchable: Vec<Record>,
     rks: Arc<Vec<String>>,
+    norm: KeyNorm,
+    range: Option<RangeSpec>,
+}
+
+#[derive(Clone)]
+struct DbEntry {
+    range: (f64, f64),
+    matched: bool,
+    r: Record,
 }
 
 impl Db {
-    fn new(file: &str, pairs: &[(String, String)]) -> Db {
+    fn new(rs: DbReader, pairs: &[(String, String)], norm: KeyNorm, range: Option<RangeSpec>) -> Db {
         let mut db = Db {
             db: HashMap::new(),
+            unmatchable: Vec::new(),
             rks: Arc::new(pairs.iter().map(|(_lk, rk)| rk.clone()).collect()),
+            norm: norm,
+            range: range,
         };
-        for line in BufReader::new(File::open(&file).unwrap()).lines() {
-            let r = Record::parse(&line.unwrap());
-            let ks = pairs.iter().map(|(lk, _rk)| r.get_path(lk)).collect();
-            db.db.entry(ks).or_insert_with(|| (false, Vec::new())).1.push(r);
+        for r in rs {
+            let range = match db.range {
+                Some(ref spec) => match spec.db_range(&r) {
+                    Some(range) => range,
+                    None => {
+                        db.unmatchable.push(r);
+                        continue;
+                    }
+                },
+                None => (0.0, 0.0),
+            };
+            let ks = pairs.iter().map(|(lk, _rk)| db.norm.apply(r.get_path(lk))).collect();
+            db.db.entry(ks).or_insert_with(Vec::new).push(DbEntry {
+                range: range,
+                matched: false,
+                r: r,
+            });
+        }
+        if db.range.is_some() {
+            for es in db.db.values_mut() {
+                es.sort_by(|e1, e2| e1.range.0.partial_cmp(&e2.range.0).unwrap());
+            }
         }
         return db;
     }
 
-    fn query(&mut self, r: &Record) -> Option<impl Iterator<Item = &Record>> {
-        let ks: Vec<_> = self.rks.iter().map(|rk| r.get_path(rk)).collect();
-        return self.db.get_mut(&ks).map(|e| {
-            e.0 = true;
-            return e.1.iter();
-        });
+    fn query(&mut self, r: &Record, dups: Dups) -> Option<Vec<Record>> {
+        let norm = &self.norm;
+        let ks: Vec<_> = self.rks.iter().map(|rk| norm.apply(r.get_path(rk))).collect();
+        let es = match self.db.get_mut(&ks) {
+            Some(es) => es,
+            None => return None,
+        };
+        let idxs: Vec<usize> = match self.range {
+            Some(ref spec) => match spec.input_value(r) {
+                Some(x) => spec.matches(es, x),
+                None => return None,
+            },
+            None => (0..es.len()).collect(),
+        };
+        if idxs.is_empty() {
+            return None;
+        }
+        // Even ambiguous matches dropped by --unique aren't leftovers.
+        for &i in idxs.iter() {
+            es[i].matched = true;
+        }
+        let rks = &self.rks;
+        let range = match dups.pick(idxs.len(), &|| key_record(rks, &ks)) {
+            Some(range) => range,
+            None => return None,
+        };
+        return Some(idxs[range].iter().map(|&i| es[i].r.clone()).collect());
     }
 
     fn leftover(&self) -> impl Iterator<Item = &Record> {
-        return self.db.values().filter(|e| !e.0).flat_map(|e| e.1.iter());
+        return self.db.values().flat_map(|es| es.iter()).filter(|e| !e.matched).map(|e| &e.r).chain(self.unmatchable.iter());
+    }
+}
+
+// The key values as a record with each at its input path, as merge joins
+// compare them.
+fn key_record(rks: &[String], ks: &[Record]) -> Record {
+    let mut k = Record::empty_hash();
+    for (rk, v) in rks.iter().zip(ks.iter()) {
+        k.set_path(rk, v.clone());
+    }
+    return k;
+}
+
+// A numeric condition on top of any --on keys, for which db records are
+// kept sorted by (start) value within each group of equal keys.  Records
+// with null values never match.
+#[derive(Clone)]
+enum RangeSpec {
+    // the greatest db value <= the input value
+    AsOf(String, String),
+    // the db value closest to the input value, the lower one on a tie
+    Nearest(String, String),
+    // db [start, end) containing the input value
+    Interval(String, String, String),
+}
+
+fn range_value(r: &Record, path: &str) -> Option<f64> {
+    let v = r.get_path(path);
+    if sorts::is_null(&v) {
+        return None;
+    }
+    return Some(v.coerce_f64());
+}
+
+impl RangeSpec {
+    fn db_range(&self, r: &Record) -> Option<(f64, f64)> {
+        return match self {
+            RangeSpec::AsOf(lk, _) | RangeSpec::Nearest(lk, _) => range_value(r, lk).map(|v| (v, v)),
+            RangeSpec::Interval(start, end, _) => match (range_value(r, start), range_value(r, end)) {
+                (Some(start), Some(end)) => Some((start, end)),
+                _ => None,
+            },
+        };
+    }
+
+    fn input_value(&self, r: &Record) -> Option<f64> {
+        return match self {
+            RangeSpec::AsOf(_, rk) | RangeSpec::Nearest(_, rk) | RangeSpec::Interval(_, _, rk) => range_value(r, rk),
+        };
+    }
+
+    // Indexes of the matching entries, which are sorted by start.  For as-of
+    // and nearest every entry with the chosen value matches.
+    fn matches(&self, es: &[DbEntry], x: f64) -> Vec<usize> {
+        let n = es.partition_point(|e| e.range.0 <= x);
+        let v = match self {
+            RangeSpec::AsOf(_, _) => {
+                if n == 0 {
+                    return Vec::new();
+                }
+                es[n - 1].range.0
+            }
+            RangeSpec::Nearest(_, _) => {
+                match (n.checked_sub(1).map(|i| es[i].range.0), es.get(n).map(|e| e.range.0)) {
+                    (Some(lo), Some(hi)) => if x - lo <= hi - x { lo } else { hi },
+                    (Some(lo), None) => lo,
+                    (None, Some(hi)) => hi,
+                    (None, None) => return Vec::new(),
+                }
+            }
+            RangeSpec::Interval(_, _, _) => {
+                return (0..n).filter(|&i| x < es[i].range.1).collect();
+            }
+        };
+        return (es.partition_point(|e| e.range.0 < v)..es.partition_point(|e| e.range.0 <= v)).collect();
+    }
+}
+
+// For --merge both sides are sorted on the join keys and compared by
+// building key records with each key value at the input's path, so the sorts
+// (lexical on each input path by default) are written against those.  Any
+// --norm applies before comparing, so both sides must be sorted on the
+// normalized values.
+#[derive(Clone)]
+struct MergeSpec {
+    source: DbSource,
+    pairs: Arc<Vec<(String, String)>>,
+    norm: KeyNorm,
+    sorts: Arc<SortOptionsValidated>,
+}
+
+impl MergeSpec {
+    fn key(&self, r: &Record, db: bool) -> Record {
+        let mut k = Record::empty_hash();
+        for (lk, rk) in self.pairs.iter() {
+            k.set_path(rk, self.norm.apply(r.get_path(if db { lk } else { rk })));
+        }
+        return k;
+    }
+}
+
+struct MergeGroup {
+    k: Record,
+    rs: Vec<Record>,
+    matched: bool,
+}
+
+// Only one group of db records with equal keys is held at a time.
+struct MergeDb {
+    spec: MergeSpec,
+    rs: DbReader,
+    next: Option<(Record, Record)>,
+    group: Option<MergeGroup>,
+    cmp: SortComparator,
+}
+
+impl MergeDb {
+    fn new(spec: MergeSpec) -> MergeDb {
+        let rs = spec.source.open();
+        let cmp = spec.sorts.comparator();
+        let mut db = MergeDb {
+            spec: spec,
+            rs: rs,
+            next: None,
+            group: None,
+            cmp: cmp,
+        };
+        db.next = db.read();
+        db.advance();
+        return db;
+    }
+
+    fn read(&mut self) -> Option<(Record, Record)> {
+        let spec = &self.spec;
+        return self.rs.next().map(|r| (spec.key(&r, true), r));
+    }
+
+    fn advance(&mut self) {
+        let (k, r) = match self.next.take() {
+            Some(e) => e,
+            None => {
+                self.group = None;
+                return;
+            }
+        };
+        let mut rs = vec![r];
+        while let Some((k2, r2)) = self.read() {
+            match self.cmp.cmp(&k, &k2) {
+                Ordering::Equal => {
+                    rs.push(r2);
+                }
+                Ordering::Less => {
+                    self.next = Some((k2, r2));
+                    break;
+                }
+                Ordering::Greater => {
+                    panic!("join --merge db isn't sorted, key {} came after {}", k2.deparse(), k.deparse());
+                }
+            }
+        }
+        self.group = Some(MergeGroup {
+            k: k,
+            rs: rs,
+            matched: false,
+        });
     }
 }
 
@@ -67,16 +470,90 @@ impl Db {
 pub struct Options {
     tru: TwoRecordUnionOption,
     fills: UnvalidatedOption<(bool, bool)>,
+    filter: OptionalOption<Filter>,
+    dups: UnvalidatedOption<Dups>,
     db: DbOption,
 }
 
-pub(crate) type Impl = OperationRegistrant<ImplBe>;
+impl OptionsValidated {
+    fn fill_db(&self) -> bool {
+        return self.fills.1 && self.filter.is_none();
+    }
+}
 
-pub(crate) type ImplBe = OperationBeForBe2<ImplBe2>;
+// --semi and --anti emit input records as is, only those with (or without)
+// a match.
+#[derive(Clone)]
+#[derive(Copy)]
+enum Filter {
+    Semi,
+    Anti,
+}
 
-pub(crate) struct ImplBe2();
+// What to do when an input record matches several db records.
+#[derive(Clone)]
+#[derive(Copy)]
+enum Dups {
+    All,
+    First,
+    Last,
+    Unique,
+    Error,
+}
 
-impl OperationBe2 for ImplBe2 {
+impl Default for Dups {
+    fn default() -> Self {
+        return Dups::All;
+    }
+}
+
+impl Dups {
+    // Which of n (> 0) matching db records to use, or None if they don't
+    // count as a match (--unique with more than one).  key is only for
+    // reporting --assert-unique failures.
+    fn pick(&self, n: usize, key: &Fn() -> Record) -> Option<Range<usize>> {
+        return match self {
+            Dups::All => Some(0..n),
+            Dups::First => Some(0..1),
+            Dups::Last => Some((n - 1)..n),
+            Dups::Unique => if n == 1 { Some(0..1) } else { None },
+            Dups::Error => {
+                if n > 1 {
+                    panic!("join --assert-unique failed, key {} matched {} db records", key().deparse(), n);
+                }
+                Some(0..1)
+            }
+        };
+    }
+}
+
+// Emits for input record r given the db records it joined to, if any.
+fn emit(o: &OptionsValidated, r: Record, r2s: Option<&[Record]>, w: &mut FnMut(Entry) -> bool) -> bool {
+    return match (o.filter, r2s) {
+        (Some(Filter::Semi), Some(_)) | (Some(Filter::Anti), None) => w(Entry::Record(r)),
+        (Some(_), _) => true,
+        (None, Some(r2s)) => {
+            for r2 in r2s {
+                if !w(Entry::Record(o.tru.union(r2.clone(), r.clone()))) {
+                    return false;
+                }
+            }
+            true
+        }
+        (None, None) => {
+            if o.fills.0 {
+                return w(Entry::Record(o.tru.union_maybe(None, Some(r))));
+            }
+            true
+        }
+    };
+}
+
+pub(crate) type Impl = OperationRegistrant<ImplBe>;
+
+pub(crate) struct ImplBe();
+
+impl OperationBe for ImplBe {
     type Options = Options;
 
     fn names() -> Vec<&'static str> {
@@ -89,58 +566,158 @@ impl OperationBe2 for ImplBe2 {
         opt.match_zero(&["left"], |p| p.fills.0 = (true, false));
         opt.match_zero(&["right"], |p| p.fills.0 = (false, true));
         opt.match_zero(&["outer"], |p| p.fills.0 = (true, true));
+        opt.match_zero(&["semi"], |p| p.filter.set(Filter::Semi));
+        opt.match_zero(&["anti"], |p| p.filter.set(Filter::Anti));
+        opt.match_zero(&["first"], |p| p.dups.0 = Dups::First);
+        opt.match_zero(&["last"], |p| p.dups.0 = Dups::Last);
+        opt.match_zero(&["unique"], |p| p.dups.0 = Dups::Unique);
+        opt.match_zero(&["assert-unique"], |p| p.dups.0 = Dups::Error);
         opt.match_n(&["on"], 2, |p, a| p.db.pairs.0.push((a[0].to_string(), a[1].to_string())));
-        opt.sub(|p| &mut p.db.file).match_extra_soft(RequiredStringOption::maybe_set_str);
+        opt.match_single(&["norm", "key-norm"], |p, a| p.db.norm.0 = KeyNorm::parse(a));
+        opt.match_n(&["asof", "as-of"], 2, |p, a| p.db.range.set(RangeSpec::AsOf(a[0].to_string(), a[1].to_string())));
+        opt.match_n(&["nearest"], 2, |p, a| p.db.range.set(RangeSpec::Nearest(a[0].to_string(), a[1].to_string())));
+        opt.match_n(&["interval"], 3, |p, a| p.db.range.set(RangeSpec::Interval(a[0].to_string(), a[1].to_string(), a[2].to_string())));
+        opt.sub(|p| &mut p.db).match_single(&["db"], DbOption::push_glob);
+        opt.match_zero(&["db-op"], |p| p.db.op_mode.0 = true);
+        opt.sub(|p| &mut p.db.merge).match_zero(&["merge"], BooleanOption::set);
+        SortOptions::options(&mut opt.sub(|p| &mut p.db.sorts), &["s", "sort"]);
+        opt.sub(|p| &mut p.db).match_extra_soft(DbOption::maybe_push);
+        opt.sub(|p| &mut p.db.op).match_extra_hard(SubOperationOption::push);
+    }
+
+    fn get_extra(o: Arc<OptionsValidated>) -> Vec<String> {
+        return o.db.extra.clone();
     }
 
     fn stream(o: Arc<OptionsValidated>) -> Stream {
-        let db = o.db.clone();
-        let o1 = o;
-        let o2 = o1.clone();
-
-        return stream::compound(
-            stream::parse(),
-            stream::closures(
-                db,
-                move |s, e, w| {
-                    match e {
-                        Entry::Bof(_file) => {
-                            return true;
+        return match o.db.mode.clone() {
+            DbMode::Hash(db) => hash_stream(o, db),
+            DbMode::Merge(spec) => merge_stream(o, MergeDb::new(spec)),
+        };
+    }
+}
+
+fn hash_stream(o: Arc<OptionsValidated>, db: Db) -> Stream {
+    let o1 = o;
+    let o2 = o1.clone();
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            db,
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    }
+                    Entry::Record(r) => {
+                        let r2s = s.query(&r, o1.dups);
+                        return emit(&o1, r, r2s.as_ref().map(|r2s| &r2s[..]), w);
+                    }
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in JoinStream");
+                    }
+                }
+            },
+            move |s, w| {
+                if o2.fill_db() {
+                    for r2 in s.leftover() {
+                        if !w(Entry::Record(o2.tru.union_maybe(Some(r2.clone()), None))) {
+                            return;
                         }
-                        Entry::Record(r) => {
-                            match s.query(&r) {
-                                Some(r2s) => {
-                                    for r2 in r2s {
-                                        if !w(Entry::Record(o1.tru.union(r2.clone(), r.clone()))) {
-                                            return false;
-                                        }
+                    }
+                }
+            },
+        ),
+    );
+}
+
+fn merge_stream(o: Arc<OptionsValidated>, db: MergeDb) -> Stream {
+    struct State {
+        o: Arc<OptionsValidated>,
+        db: MergeDb,
+        prev: Option<Record>,
+    }
+
+    impl State {
+        // Finishes the current db group, emitting it if unmatched and
+        // right-filling.
+        fn flush(&mut self, w: &mut FnMut(Entry) -> bool) -> bool {
+            let g = self.db.group.take().unwrap();
+            if !g.matched && self.o.fill_db() {
+                for r2 in g.rs {
+                    if !w(Entry::Record(self.o.tru.union_maybe(Some(r2), None))) {
+                        return false;
+                    }
+                }
+            }
+            self.db.advance();
+            return true;
+        }
+    }
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                o: o,
+                db: db,
+                prev: None,
+            },
+            |s, e, w| {
+                match e {
+                    Entry::Bof(_file) => {
+                        return true;
+                    }
+                    Entry::Record(r) => {
+                        let k = s.db.spec.key(&r, false);
+                        if let Some(ref prev) = s.prev {
+                            if s.db.cmp.cmp(prev, &k) == Ordering::Greater {
+                                panic!("join --merge input isn't sorted, key {} came after {}", k.deparse(), prev.deparse());
+                            }
+                        }
+                        s.prev = Some(k.clone());
+
+                        loop {
+                            let ord = match s.db.group {
+                                Some(ref g) => s.db.cmp.cmp(&g.k, &k),
+                                None => Ordering::Greater,
+                            };
+                            match ord {
+                                Ordering::Less => {
+                                    if !s.flush(w) {
+                                        return false;
                                     }
                                 }
-                                None => {
-                                    if o1.fills.0 {
-                                        if !w(Entry::Record(o1.tru.union_maybe(None, Some(r)))) {
-                                            return false;
-                                        }
-                                    }
+                                Ordering::Equal => {
+                                    let g = s.db.group.as_mut().unwrap();
+                                    // Even when dropped by --unique.
+                                    g.matched = true;
+                                    return match s.o.dups.pick(g.rs.len(), &|| k.clone()) {
+                                        Some(range) => emit(&s.o, r, Some(&g.rs[range]), w),
+                                        None => emit(&s.o, r, None, w),
+                                    };
+                                }
+                                Ordering::Greater => {
+                                    return emit(&s.o, r, None, w);
                                 }
                             }
-                            return true;
-                        }
-                        Entry::Line(_line) => {
-                            panic!("Unexpected line in JoinStream");
                         }
                     }
-                },
-                move |s, w| {
-                    if o2.fills.1 {
-                        for r2 in s.leftover() {
-                            if !w(Entry::Record(o2.tru.union_maybe(Some(r2.clone()), None))) {
-                                return;
-                            }
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in JoinStream");
+                    }
+                }
+            },
+            |mut s, w| {
+                if s.o.fill_db() {
+                    while s.db.group.is_some() {
+                        if !s.flush(w) {
+                            return;
                         }
                     }
-                },
-            ),
-        );
-    }
+                }
+            },
+        ),
+    );
 }
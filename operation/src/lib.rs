@@ -3,10 +3,13 @@ extern crate bgop;
 extern crate clumper;
 extern crate deaggregator;
 extern crate executor;
+extern crate glob;
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
 extern crate opts;
+extern crate rand;
+extern crate rand_chacha;
 extern crate record;
 extern crate regex;
 #[macro_use]
@@ -17,6 +20,12 @@ extern crate validates;
 #[macro_use]
 extern crate validates_derive;
 
+mod checkpoint;
+pub(crate) use self::checkpoint::Bucket;
+pub(crate) use self::checkpoint::CheckpointOptions;
+pub(crate) use self::checkpoint::Checkpointable;
+pub(crate) use self::checkpoint::Checkpointer;
+
 mod tru;
 pub(crate) use self::tru::TwoRecordUnionOption;
 
@@ -25,9 +34,11 @@ pub(crate) use self::clumper_options::ClumperOptions;
 
 mod subop_options;
 pub(crate) use self::subop_options::SubOperationOption;
+pub(crate) use self::subop_options::SubOperationOptionValidated;
 
 mod sort_options;
 pub(crate) use self::sort_options::GenericSortBucket;
+pub(crate) use self::sort_options::SortComparator;
 pub(crate) use self::sort_options::SortOptions;
 pub(crate) use self::sort_options::SortOptionsValidated;
 
@@ -61,6 +72,7 @@ registry! {
     multiplex,
     parse,
     provenance,
+    sample,
     shell,
     sort,
     to_ptable,
@@ -177,3 +189,6 @@ impl<B: OperationBe2> OperationBe for OperationBeForBe2<B> {
         return B::stream(p.p.clone());
     }
 }
+
+#[cfg(test)]
+mod tests;
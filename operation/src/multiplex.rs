@@ -1,9 +1,20 @@
 use opts::parser::OptParserView;
+use opts::vals::BooleanOption;
+use opts::vals::OptionalUsizeOption;
+use record::Record;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use stream::Entry;
 use stream::Stream;
 use super::ClumperOptions;
 use super::OperationBe;
 use super::OperationRegistrant;
+use super::StreamWrapper;
 use super::SubOperationOption;
 use validates::Validates;
 
@@ -12,6 +23,8 @@ use validates::Validates;
 pub struct Options {
     cl: ClumperOptions,
     op: SubOperationOption,
+    threads: OptionalUsizeOption,
+    contiguous: BooleanOption,
 }
 
 pub(crate) type Impl = OperationRegistrant<ImplBe>;
@@ -28,6 +41,9 @@ impl OperationBe for ImplBe {
     fn options<'a>(opt: &mut OptParserView<'a, Options>) {
         opt.sub(|p| &mut p.op).match_extra_hard(SubOperationOption::push);
         ClumperOptions::options(&mut opt.sub(|p| &mut p.cl));
+        opt.sub(|p| &mut p.threads).match_single(&["threads"], OptionalUsizeOption::parse);
+        opt.sub(|p| &mut p.contiguous).match_zero(&["contiguous"], BooleanOption::set);
+        opt.sub(|p| &mut p.contiguous).match_zero(&["no-contiguous"], BooleanOption::clear);
     }
 
     fn get_extra(o: Arc<OptionsValidated>) -> Vec<String> {
@@ -35,15 +51,231 @@ impl OperationBe for ImplBe {
     }
 
     fn stream(o: Arc<OptionsValidated>) -> Stream {
+        if let Some(n) = o.threads {
+            return threaded_stream(o.clone(), n);
+        }
+
         let o2 = o.clone();
         return o.cl.stream(move |bucket| {
-            let s = stream::transform_records(move |mut r| {
-                for (path, v) in &bucket {
-                    r.set_path(&path, v.clone());
-                }
-                return r;
-            });
-            return stream::compound(o2.op.wr.stream(), s);
+            return bucket_stream(&o2.op.for_bucket(&bucket), bucket);
         });
     }
 }
+
+fn bucket_stream(wr: &StreamWrapper, bucket: Vec<(Arc<str>, Record)>) -> Stream {
+    let s = stream::transform_records(move |mut r| {
+        for (path, v) in &bucket {
+            r.set_path(&path, v.clone());
+        }
+        return r;
+    });
+    return stream::compound(wr.stream(), s);
+}
+
+enum ToWorker {
+    Open(usize, Arc<StreamWrapper>, Vec<(Arc<str>, Record)>),
+    Entry(usize, Entry),
+    Close(usize),
+}
+
+struct Pool {
+    txs: Vec<mpsc::SyncSender<ToWorker>>,
+    // None once downstream has refused more
+    rx: Option<mpsc::Receiver<Vec<Entry>>>,
+    threads: Vec<thread::JoinHandle<()>>,
+    next_id: usize,
+}
+
+impl Pool {
+    // Both directions are bounded so while a worker is backed up we keep
+    // ferrying output lest it block on us as we block on it.
+    fn send(&mut self, id: usize, mut m: ToWorker, w: &mut FnMut(Entry) -> bool) -> bool {
+        loop {
+            if self.rx.is_none() {
+                return false;
+            }
+            let n = self.txs.len();
+            match self.txs[id % n].try_send(m) {
+                Ok(()) => {
+                    return true;
+                }
+                Err(mpsc::TrySendError::Full(m2)) => {
+                    m = m2;
+                    let es = self.rx.as_ref().unwrap().recv_timeout(Duration::from_millis(1));
+                    if let Ok(es) = es {
+                        if !self.forward(es, w) {
+                            return false;
+                        }
+                    }
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => {
+                    panic!("multiplex worker died");
+                }
+            }
+        }
+    }
+
+    fn forward(&mut self, es: Vec<Entry>, w: &mut FnMut(Entry) -> bool) -> bool {
+        for e in es {
+            if !w(e) {
+                self.shutdown();
+                return false;
+            }
+        }
+        return true;
+    }
+
+    fn drain(&mut self, w: &mut FnMut(Entry) -> bool) -> bool {
+        loop {
+            let es = match self.rx {
+                Some(ref rx) => match rx.try_recv() {
+                    Ok(es) => es,
+                    Err(_) => return true,
+                },
+                None => return false,
+            };
+            if !self.forward(es, w) {
+                return false;
+            }
+        }
+    }
+
+    // Hangs up on the workers and forwards whatever they have left.
+    fn finish(&mut self, w: &mut FnMut(Entry) -> bool) {
+        self.txs.clear();
+        loop {
+            let es = match self.rx {
+                Some(ref rx) => match rx.recv() {
+                    Ok(es) => es,
+                    Err(_) => break,
+                },
+                None => break,
+            };
+            if !self.forward(es, w) {
+                break;
+            }
+        }
+        self.shutdown();
+    }
+
+    // Hangs up on the workers in both directions, dropping anything they
+    // still have, and waits for them to exit.
+    fn shutdown(&mut self) {
+        self.txs.clear();
+        self.rx = None;
+        for t in self.threads.drain(..) {
+            t.join().unwrap();
+        }
+    }
+}
+
+// Buckets are handed out to the workers round robin by when they open.  The
+// clumping itself stays on this thread, as does merging worker output, which
+// is passed back as it comes or, with --contiguous, a bucket at a time when
+// it closes.
+fn threaded_stream(o: Arc<OptionsValidated>, n: usize) -> Stream {
+    assert!(n > 0, "multiplex --threads needs a positive count");
+
+    let (out_tx, out_rx) = mpsc::sync_channel(1024);
+    let mut txs = Vec::new();
+    let mut threads = Vec::new();
+    for _ in 0..n {
+        let (tx, rx) = mpsc::sync_channel(1024);
+        let out_tx = out_tx.clone();
+        let contiguous = o.contiguous;
+        txs.push(tx);
+        threads.push(thread::spawn(move || {
+            let mut streams = HashMap::new();
+            for m in rx.iter() {
+                match m {
+                    ToWorker::Open(id, wr, bucket) => {
+                        streams.insert(id, (bucket_stream(&wr, bucket), Vec::new()));
+                    },
+                    ToWorker::Entry(id, e) => {
+                        let (s, buf) = streams.get_mut(&id).unwrap();
+                        // Disregard flow since one substream ending does not
+                        // mean we're done.
+                        s.write(e, &mut |e| {
+                            buf.push(e);
+                            return true;
+                        });
+                        if !contiguous && !buf.is_empty() {
+                            if out_tx.send(buf.drain(..).collect()).is_err() {
+                                // downstream is done with us
+                                return;
+                            }
+                        }
+                    },
+                    ToWorker::Close(id) => {
+                        let (s, mut buf) = streams.remove(&id).unwrap();
+                        s.close(&mut |e| {
+                            buf.push(e);
+                            return true;
+                        });
+                        if out_tx.send(buf).is_err() {
+                            return;
+                        }
+                    },
+                }
+            }
+        }));
+    }
+
+    let pool = Rc::new(RefCell::new(Pool {
+        txs: txs,
+        rx: Some(out_rx),
+        threads: threads,
+        next_id: 0,
+    }));
+
+    let pool2 = pool.clone();
+    let o2 = o.clone();
+    let s = o.cl.stream(move |bucket| {
+        let wr = o2.op.for_bucket(&bucket);
+        let id = {
+            let mut pool = pool2.borrow_mut();
+            let id = pool.next_id;
+            pool.next_id += 1;
+            id
+        };
+        let pool = pool2.clone();
+        let pool2 = pool2.clone();
+        // Opening waits for the first write or close since only then do we
+        // have somewhere to ferry output to should the worker be backed up.
+        return stream::closures(
+            Some((wr, bucket)),
+            move |s, e, w| {
+                let mut pool = pool.borrow_mut();
+                if let Some((wr, bucket)) = s.take() {
+                    if !pool.send(id, ToWorker::Open(id, wr, bucket), w) {
+                        return false;
+                    }
+                }
+                return pool.send(id, ToWorker::Entry(id, e), w) && pool.drain(w);
+            },
+            move |s, w| {
+                let mut pool = pool2.borrow_mut();
+                if let Some((wr, bucket)) = s {
+                    if !pool.send(id, ToWorker::Open(id, wr, bucket), w) {
+                        return;
+                    }
+                }
+                if pool.send(id, ToWorker::Close(id), w) {
+                    pool.drain(w);
+                }
+            },
+        );
+    });
+
+    return stream::closures(
+        (s, pool),
+        |(s, pool), e, w| {
+            let ret = s.write(e, w);
+            return pool.borrow_mut().drain(w) && ret;
+        },
+        |(s, pool), w| {
+            s.close(w);
+            pool.borrow_mut().finish(w);
+        },
+    );
+}
@@ -0,0 +1,180 @@
+use opts::parser::OptParserView;
+use opts::vals::OptionalOption;
+use opts::vals::OptionalUsizeOption;
+use opts::vals::StringVecOption;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use record::Record;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::OperationBe2;
+use super::OperationBeForBe2;
+use super::OperationRegistrant;
+use validates::Validates;
+
+#[derive(Default)]
+struct ModeOption {
+    n: OptionalUsizeOption,
+    rate: OptionalOption<f64>,
+    keys: StringVecOption,
+}
+
+#[derive(Clone)]
+enum Mode {
+    Reservoir(usize, Vec<String>),
+    Rate(f64),
+}
+
+impl Validates for ModeOption {
+    type Target = Mode;
+
+    fn validate(self) -> Mode {
+        let keys = self.keys.validate();
+        return match (self.n.validate(), self.rate.validate()) {
+            (Some(n), None) => Mode::Reservoir(n, keys),
+            (None, Some(rate)) => {
+                assert!(keys.is_empty(), "sample -k only works with -n");
+                assert!(rate >= 0.0 && rate <= 1.0, "sample --rate must be between 0 and 1");
+                Mode::Rate(rate)
+            },
+            _ => panic!("sample needs exactly one of -n and --rate"),
+        };
+    }
+}
+
+#[derive(Default)]
+#[derive(Validates)]
+pub struct Options {
+    mode: ModeOption,
+    seed: OptionalOption<u64>,
+}
+
+pub(crate) type Impl = OperationRegistrant<ImplBe>;
+
+pub(crate) type ImplBe = OperationBeForBe2<ImplBe2>;
+
+pub(crate) struct ImplBe2();
+
+impl OperationBe2 for ImplBe2 {
+    type Options = Options;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["sample"];
+    }
+
+    fn options<'a>(opt: &mut OptParserView<'a, Options>) {
+        opt.sub(|p| &mut p.mode.n).match_single(&["n"], OptionalUsizeOption::parse);
+        opt.sub(|p| &mut p.mode.rate).match_single(&["rate"], |p, a| p.set(a.parse().unwrap()));
+        opt.sub(|p| &mut p.mode.keys).match_single(&["k", "key"], StringVecOption::push_split);
+        opt.sub(|p| &mut p.seed).match_single(&["seed"], |p, a| p.set(a.parse().unwrap()));
+    }
+
+    // -n keeps a uniform sample of (up to) n records (per key with -k),
+    // emitted in input order at close, while --rate keeps each record
+    // independently with that probability as it goes.  The same --seed (and
+    // input) always gives the same sample.
+    fn stream(o: Arc<OptionsValidated>) -> Stream {
+        let rng = match o.seed {
+            Some(seed) => ChaChaRng::seed_from_u64(seed),
+            None => ChaChaRng::seed_from_u64(rand::thread_rng().gen()),
+        };
+
+        match o.mode {
+            Mode::Reservoir(n, ref keys) => {
+                return reservoir(Arc::new(keys.clone()), n, rng);
+            },
+            Mode::Rate(rate) => {
+                return stream::compound(
+                    stream::parse(),
+                    stream::closures(
+                        rng,
+                        move |rng, e, w| {
+                            if let Entry::Record(_) = e {
+                                if !rng.gen_bool(rate) {
+                                    return true;
+                                }
+                            }
+                            return w(e);
+                        },
+                        |_rng, _w| {
+                        },
+                    ),
+                );
+            },
+        }
+    }
+}
+
+struct Reservoir {
+    seen: usize,
+    rs: Vec<(usize, Record)>,
+}
+
+fn reservoir(keys: Arc<Vec<String>>, n: usize, rng: ChaChaRng) -> Stream {
+    struct State {
+        keys: Arc<Vec<String>>,
+        rng: ChaChaRng,
+        i: usize,
+        reservoirs: HashMap<Vec<Record>, Reservoir>,
+    }
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                keys: keys,
+                rng: rng,
+                i: 0,
+                reservoirs: HashMap::new(),
+            },
+            move |s, e, w| {
+                match e {
+                    Entry::Bof(file) => {
+                        return w(Entry::Bof(file));
+                    }
+                    Entry::Record(r) => {
+                        let i = s.i;
+                        s.i += 1;
+
+                        let key: Vec<_> = s.keys.iter().map(|k| r.get_path(k)).collect();
+                        let res = s.reservoirs.entry(key).or_insert_with(|| {
+                            return Reservoir {
+                                seen: 0,
+                                rs: Vec::new(),
+                            };
+                        });
+                        res.seen += 1;
+                        if res.rs.len() < n {
+                            res.rs.push((i, r));
+                        }
+                        else {
+                            let j = s.rng.gen_range(0, res.seen);
+                            if j < n {
+                                res.rs[j] = (i, r);
+                            }
+                        }
+                        return true;
+                    }
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in SampleStream");
+                    }
+                }
+            },
+            |s, w| {
+                let mut rs = BTreeMap::new();
+                for (_, res) in s.reservoirs.into_iter() {
+                    rs.extend(res.rs);
+                }
+                for (_, r) in rs.into_iter() {
+                    if !w(Entry::Record(r)) {
+                        return;
+                    }
+                }
+            },
+        ),
+    );
+}
@@ -1,7 +1,24 @@
 use opts::parser::OptParserView;
+use opts::vals::BooleanOption;
+use opts::vals::OptionalStringOption;
 use opts::vals::OptionalUsizeOption;
+use opts::vals::StringVecOption;
+use record::Record;
 use registry::Registrant;
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::ErrorKind;
+use std::io::Lines;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use stream::Entry;
 use stream::Stream;
 use super::GenericSortBucket;
@@ -16,6 +33,12 @@ use validates::Validates;
 pub struct Options {
     sorts: SortOptions,
     partial: OptionalUsizeOption,
+    rank: OptionalStringOption,
+    percentile: OptionalStringOption,
+    dense: BooleanOption,
+    spill: OptionalUsizeOption,
+    tmpdir: OptionalStringOption,
+    group: StringVecOption,
 }
 
 pub(crate) type Impl = OperationRegistrant<ImplBe>;
@@ -44,15 +67,30 @@ impl OperationBe2 for ImplBe2 {
             }
         });
         opt.sub(|p| &mut p.partial).match_single(&["p", "partial"], OptionalUsizeOption::parse);
+        opt.sub(|p| &mut p.rank).match_single(&["rank"], OptionalStringOption::set_str);
+        opt.sub(|p| &mut p.percentile).match_single(&["percentile"], OptionalStringOption::set_str);
+        opt.sub(|p| &mut p.dense).match_zero(&["dense"], BooleanOption::set);
+        opt.sub(|p| &mut p.dense).match_zero(&["competition"], BooleanOption::clear);
+        opt.sub(|p| &mut p.spill).match_single(&["spill"], OptionalUsizeOption::parse);
+        opt.sub(|p| &mut p.tmpdir).match_single(&["tmpdir"], OptionalStringOption::set_str);
+        opt.sub(|p| &mut p.group).match_single(&["g", "group"], StringVecOption::push_split);
     }
 
     fn stream(o: Arc<OptionsValidated>) -> Stream {
+        if !o.group.is_empty() {
+            return grouped_stream(o);
+        }
+
         struct State {
             o: Arc<OptionsValidated>,
-            rs: GenericSortBucket<()>,
+            // by input index, which orders ties and (for shuffle) keys
+            // records the same whether or not they're spilled
+            rs: GenericSortBucket<usize>,
+            n: usize,
+            runs: Vec<Run>,
         }
 
-        let rs = o.sorts.new_bucket();
+        let rs = new_bucket(&o);
 
         return stream::compound(
             stream::parse(),
@@ -60,18 +98,27 @@ impl OperationBe2 for ImplBe2 {
                 State {
                     o: o,
                     rs: rs,
+                    n: 0,
+                    runs: Vec::new(),
                 },
                 |s, e, _w| {
                     match e {
                         Entry::Bof(_file) => {
                         }
                         Entry::Record(r) => {
-                            s.rs.add(r, ());
+                            s.rs.add_at(r, s.n, s.n);
+                            s.n += 1;
                             if let Some(limit) = s.o.partial {
                                 if s.rs.size() > limit {
                                     s.rs.remove_last();
                                 }
                             }
+                            if let Some(spill) = s.o.spill {
+                                if s.rs.size() >= spill {
+                                    let rs = std::mem::replace(&mut s.rs, new_bucket(&s.o));
+                                    s.runs.push(Run::spill(&s.o, rs));
+                                }
+                            }
                         }
                         Entry::Line(_line) => {
                             panic!("Unexpected line in SortStream");
@@ -79,14 +126,265 @@ impl OperationBe2 for ImplBe2 {
                     }
                     return true;
                 },
-                |mut s, w| {
-                    while let Some((r, _)) = s.rs.remove_first() {
-                        if !w(Entry::Record(r)) {
-                            return;
-                        }
+                |s, w| {
+                    let State { o, rs, n, mut runs } = s;
+
+                    if runs.is_empty() {
+                        emit(&o, rs, n, w);
+                        return;
+                    }
+
+                    if rs.size() > 0 {
+                        runs.push(Run::spill(&o, rs));
                     }
+                    let mut ranker = Ranker::new(&o, n);
+                    merge(&o, runs, &mut |r, tie| w(Entry::Record(ranker.annotate(r, tie))));
                 },
             ),
         );
     }
 }
+
+fn wants_ties(o: &OptionsValidated) -> bool {
+    return o.rank.is_some() || o.percentile.is_some();
+}
+
+// Only ranking needs to know about ties.
+fn new_bucket<T>(o: &OptionsValidated) -> GenericSortBucket<T> {
+    if wants_ties(o) {
+        return o.sorts.new_grouped_bucket();
+    }
+    return o.sorts.new_bucket();
+}
+
+// Emits a bucket of n records (before --partial), returning false if the
+// output is done.
+fn emit<T>(o: &Arc<OptionsValidated>, mut rs: GenericSortBucket<T>, n: usize, w: &mut FnMut(Entry) -> bool) -> bool {
+    if !wants_ties(o) {
+        while let Some((r, _)) = rs.remove_first() {
+            if !w(Entry::Record(r)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    let mut ranker = Ranker::new(o, n);
+    let mut prev_group = None;
+    while let Some((r, _, group)) = rs.remove_first_grouped() {
+        let tie = prev_group == Some(group);
+        prev_group = Some(group);
+        if !w(Entry::Record(ranker.annotate(r, tie))) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// With --group each group is sorted (and limited by --partial, and ranked)
+// separately, and the groups are emitted one after another in the order
+// they were first seen.
+fn grouped_stream(o: Arc<OptionsValidated>) -> Stream {
+    struct State {
+        o: Arc<OptionsValidated>,
+        idxs: HashMap<Vec<Record>, usize>,
+        groups: Vec<(GenericSortBucket<()>, usize)>,
+    }
+
+    assert!(o.spill.is_none(), "sort --spill doesn't work with --group");
+
+    return stream::compound(
+        stream::parse(),
+        stream::closures(
+            State {
+                o: o,
+                idxs: HashMap::new(),
+                groups: Vec::new(),
+            },
+            |s, e, _w| {
+                match e {
+                    Entry::Bof(_file) => {
+                    }
+                    Entry::Record(r) => {
+                        let key: Vec<_> = s.o.group.iter().map(|k| r.get_path(k)).collect();
+                        let groups = &mut s.groups;
+                        let o = &s.o;
+                        let idx = *s.idxs.entry(key).or_insert_with(|| {
+                            groups.push((new_bucket(o), 0));
+                            return groups.len() - 1;
+                        });
+
+                        let (ref mut rs, ref mut n) = s.groups[idx];
+                        *n += 1;
+                        rs.add(r, ());
+                        if let Some(limit) = s.o.partial {
+                            if rs.size() > limit {
+                                rs.remove_last();
+                            }
+                        }
+                    }
+                    Entry::Line(_line) => {
+                        panic!("Unexpected line in SortStream");
+                    }
+                }
+                return true;
+            },
+            |s, w| {
+                for (rs, n) in s.groups.into_iter() {
+                    if !emit(&s.o, rs, n, w) {
+                        return;
+                    }
+                }
+            },
+        ),
+    );
+}
+
+// Ranks start at 1 and ties (records equal on every sort) share the same
+// rank, with the next rank either skipping past them (competition, "1224")
+// or not (dense, "1223").  Percentile is the competition rank as a
+// percentage of all records, including any dropped by --partial.
+struct Ranker {
+    o: Arc<OptionsValidated>,
+    n: usize,
+    i: usize,
+    crank: usize,
+    drank: usize,
+}
+
+impl Ranker {
+    fn new(o: &Arc<OptionsValidated>, n: usize) -> Ranker {
+        return Ranker {
+            o: o.clone(),
+            n: n,
+            i: 0,
+            crank: 0,
+            drank: 0,
+        };
+    }
+
+    fn annotate(&mut self, mut r: Record, tie: bool) -> Record {
+        self.i += 1;
+        if !tie {
+            self.crank = self.i;
+            self.drank += 1;
+        }
+        let rank = match self.o.dense {
+            true => self.drank,
+            false => self.crank,
+        };
+        if let Some(ref path) = self.o.rank {
+            r.set_path(path, Record::from(rank as i64));
+        }
+        if let Some(ref path) = self.o.percentile {
+            r.set_path(path, Record::from(100.0 * (self.crank as f64) / (self.n as f64)));
+        }
+        return r;
+    }
+}
+
+// A sorted run of records spilled to a temp file, one per line as its input
+// index, a tab, and the JSON record.
+pub(crate) struct Run {
+    path: PathBuf,
+}
+
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl Run {
+    fn spill(o: &OptionsValidated, mut rs: GenericSortBucket<usize>) -> Run {
+        let dir = match o.tmpdir {
+            Some(ref dir) => PathBuf::from(dir),
+            None => std::env::temp_dir(),
+        };
+        let (f, run) = Run::create(&dir, RUN_COUNTER.fetch_add(1, Ordering::SeqCst));
+
+        let mut f = BufWriter::new(f);
+        while let Some((r, i)) = rs.remove_first() {
+            writeln!(f, "{}\t{}", i, r.deparse()).unwrap();
+        }
+        f.flush().unwrap();
+
+        return run;
+    }
+
+    // The tmp dir may be shared so never reuse (or follow a link at) an
+    // existing path, just try the next name.  The Run is returned with the
+    // file so it's removed even if writing fails.
+    pub(crate) fn create(dir: &Path, id: usize) -> (File, Run) {
+        let mut attempt = 0;
+        loop {
+            let path = dir.join(format!("r4-sort-{}-{}-{}", std::process::id(), id, attempt));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(f) => {
+                    return (f, Run {
+                        path: path,
+                    });
+                }
+                Err(ref e) if e.kind() == ErrorKind::AlreadyExists => {
+                    attempt += 1;
+                }
+                Err(e) => panic!("Could not create sort spill file {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        return &self.path;
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// k-way merge of the runs, using a sort bucket holding the head of each run
+// to pick the next.  Stops early on --partial or if f returns false.
+fn merge(o: &Arc<OptionsValidated>, runs: Vec<Run>, f: &mut FnMut(Record, bool) -> bool) {
+    // Grouped for its ordering of ties by input index.
+    let mut heads = o.sorts.new_grouped_bucket();
+    let mut readers: Vec<Lines<BufReader<File>>> = Vec::new();
+
+    let next = |heads: &mut GenericSortBucket<usize>, readers: &mut Vec<Lines<BufReader<File>>>, idx: usize| {
+        if let Some(line) = readers[idx].next() {
+            let line = line.unwrap();
+            let tab = line.find('\t').unwrap();
+            heads.add_at(Record::parse(&line[(tab + 1)..]), idx, line[0..tab].parse().unwrap());
+        }
+    };
+
+    for (idx, run) in runs.iter().enumerate() {
+        readers.push(BufReader::new(File::open(&run.path).unwrap()).lines());
+        next(&mut heads, &mut readers, idx);
+    }
+
+    let wants_ties = wants_ties(o);
+    let mut cmp = o.sorts.comparator();
+    let mut prev: Option<Record> = None;
+    let mut emitted = 0;
+    while let Some((r, idx, _)) = heads.remove_first_grouped() {
+        if let Some(limit) = o.partial {
+            if emitted >= limit {
+                return;
+            }
+        }
+        emitted += 1;
+
+        // Group ids can't be trusted across refills (the tied head may have
+        // been alone in its bucket), so compare directly.
+        let tie = match prev {
+            Some(ref prev) if wants_ties => cmp.ties(prev, &r),
+            _ => false,
+        };
+        if wants_ties {
+            prev = Some(r.clone());
+        }
+        if !f(r, tie) {
+            return;
+        }
+
+        next(&mut heads, &mut readers, idx);
+    }
+}
@@ -3,7 +3,11 @@ use opts::vals::UnvalidatedOption;
 use record::Record;
 use sorts::BoxedSort;
 use sorts::bucket::SortBucket;
+use sorts::bucket::SortBucketSide;
 use sorts::bucket::VecDequeSortBucket;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::rc::Rc;
 use validates::Validates;
@@ -21,11 +25,49 @@ impl SortOptions {
     pub fn push(&mut self, s: BoxedSort) {
         (self.0).0.push(s);
     }
+
+    pub fn is_empty(&self) -> bool {
+        return (self.0).0.is_empty();
+    }
+}
+
+// The innermost buckets hold records that tie on every sort, so tagging
+// records with which one they went in tells us about ties.  They're ordered
+// by index (rather than as added) so merging runs can keep ties in input
+// order.
+struct GroupSortBucket {
+    id: usize,
+    groups: Rc<RefCell<HashMap<usize, usize>>>,
+    rs: BTreeMap<usize, Record>,
+}
+
+impl SortBucket for GroupSortBucket {
+    fn add(&mut self, r: Record, i: usize) {
+        self.groups.borrow_mut().insert(i, self.id);
+        self.rs.insert(i, r);
+    }
+
+    fn remove_from(&mut self, side: SortBucketSide) -> Option<(Record, usize)> {
+        let i = match side {
+            SortBucketSide::Front() => self.rs.keys().next(),
+            SortBucketSide::Back() => self.rs.keys().next_back(),
+        };
+        let i = match i {
+            Some(&i) => i,
+            None => return None,
+        };
+        return Some((self.rs.remove(&i).unwrap(), i));
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.rs.is_empty();
+    }
 }
 
 pub struct GenericSortBucket<T> {
     ts: HashMap<usize, T>,
     i: usize,
+    groups: Rc<RefCell<HashMap<usize, usize>>>,
     bucket: Box<SortBucket>,
 }
 
@@ -38,9 +80,29 @@ impl<T> GenericSortBucket<T> {
         self.bucket.add(r, i);
     }
 
+    // Add with an explicit index, which orders ties in grouped buckets.
+    // Don't mix with add().
+    pub fn add_at(&mut self, r: Record, t: T, i: usize) {
+        self.ts.insert(i, t);
+        self.bucket.add(r, i);
+    }
+
     fn removed(&mut self, e: Option<(Record, usize)>) -> Option<(Record, T)> {
         return match e {
-            Some((r, i)) => Some((r, self.ts.remove(&i).unwrap())),
+            Some((r, i)) => {
+                self.groups.borrow_mut().remove(&i);
+                Some((r, self.ts.remove(&i).unwrap()))
+            },
+            None => None,
+        };
+    }
+
+    fn removed_grouped(&mut self, e: Option<(Record, usize)>) -> Option<(Record, T, usize)> {
+        return match e {
+            Some((r, i)) => {
+                let g = self.groups.borrow_mut().remove(&i).unwrap();
+                Some((r, self.ts.remove(&i).unwrap(), g))
+            },
             None => None,
         };
     }
@@ -55,6 +117,13 @@ impl<T> GenericSortBucket<T> {
         return self.removed(e);
     }
 
+    // Also returns a group id which is the same for consecutive records
+    // that tie on every sort.  Only for buckets from new_grouped_bucket().
+    pub fn remove_first_grouped(&mut self) -> Option<(Record, T, usize)> {
+        let e = self.bucket.remove_first();
+        return self.removed_grouped(e);
+    }
+
     pub fn size(&self) -> usize {
         return self.ts.len();
     }
@@ -62,8 +131,30 @@ impl<T> GenericSortBucket<T> {
 
 impl SortOptionsValidated {
     pub fn new_bucket<T>(&self) -> GenericSortBucket<T> {
-        let f: Rc<Fn() -> Box<SortBucket>> = Rc::new(VecDequeSortBucket::new);
+        return self.new_bucket_with(Rc::new(RefCell::new(HashMap::new())), Rc::new(VecDequeSortBucket::new));
+    }
+
+    // A bucket that also tracks ties and keeps them ordered by index (see
+    // GroupSortBucket), which costs a map entry per record so is only for
+    // when that's needed.
+    pub fn new_grouped_bucket<T>(&self) -> GenericSortBucket<T> {
+        let groups = Rc::new(RefCell::new(HashMap::new()));
+        let next_id = RefCell::new(0);
+        let groups2 = groups.clone();
+        let f: Rc<Fn() -> Box<SortBucket>> = Rc::new(move || {
+            let mut next_id = next_id.borrow_mut();
+            let id = *next_id;
+            *next_id += 1;
+            return Box::new(GroupSortBucket {
+                id: id,
+                groups: groups2.clone(),
+                rs: BTreeMap::new(),
+            });
+        });
+        return self.new_bucket_with(groups, f);
+    }
 
+    fn new_bucket_with<T>(&self, groups: Rc<RefCell<HashMap<usize, usize>>>, f: Rc<Fn() -> Box<SortBucket>>) -> GenericSortBucket<T> {
         let f = self.0.iter().rev().fold(f, |f, sort| {
             let sort = sort.clone();
             return Rc::new(move || sort.new_bucket(f.clone()));
@@ -72,7 +163,37 @@ impl SortOptionsValidated {
         return GenericSortBucket {
             ts: HashMap::new(),
             i: 0,
+            groups: groups,
             bucket: f(),
         };
     }
+
+    // For comparing records pairwise, build once and reuse.
+    pub fn comparator(&self) -> SortComparator {
+        return SortComparator(self.new_grouped_bucket());
+    }
+}
+
+// Compares records by running them through a bucket of their own, which is
+// left empty after each comparison.
+pub struct SortComparator(GenericSortBucket<Ordering>);
+
+impl SortComparator {
+    // Orders a and b by the sorts, Equal meaning they tie on every sort.
+    pub fn cmp(&mut self, a: &Record, b: &Record) -> Ordering {
+        let bucket = &mut self.0;
+        bucket.add(a.clone(), Ordering::Less);
+        bucket.add(b.clone(), Ordering::Greater);
+        let (_, o, g1) = bucket.remove_first_grouped().unwrap();
+        let (_, _, g2) = bucket.remove_first_grouped().unwrap();
+        if g1 == g2 {
+            return Ordering::Equal;
+        }
+        return o;
+    }
+
+    // Whether a and b tie on every sort.
+    pub fn ties(&mut self, a: &Record, b: &Record) -> bool {
+        return self.cmp(a, b) == Ordering::Equal;
+    }
 }
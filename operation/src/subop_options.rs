@@ -1,3 +1,5 @@
+use record::Record;
+use record::RecordTrait;
 use std::sync::Arc;
 use super::StreamWrapper;
 use validates::Validates;
@@ -19,12 +21,14 @@ impl Validates for SubOperationOption {
     type Target = SubOperationOptionValidated;
 
     fn validate(mut self) -> SubOperationOptionValidated {
+        let args = self.0.clone();
         let name = self.0.remove(0);
         let op = super::REGISTRY.find(&name, &[]);
         let wr = op.parse(&mut self.0);
         return SubOperationOptionValidated {
             extra: self.0,
             wr: Arc::new(wr),
+            args: args,
         };
     }
 }
@@ -33,4 +37,44 @@ impl Validates for SubOperationOption {
 pub struct SubOperationOptionValidated {
     pub extra: Vec<String>,
     pub wr: Arc<StreamWrapper>,
+    args: Vec<String>,
+}
+
+const BUCKET_PREFIX: &str = "{{bucket:";
+
+impl SubOperationOptionValidated {
+    // The sub-operation with "{{bucket:<path>}}" in its arguments replaced
+    // with that bucket value (strings as is, anything else as JSON), e.g.  to
+    // name an output file after the key.  This is plain text templating for
+    // names and the like: values are spliced in verbatim with no quoting, so
+    // it must not be used inside executor code where a key containing a
+    // quote breaks the parse or, worse, becomes code.
+    pub fn for_bucket(&self, bucket: &Vec<(Arc<str>, Record)>) -> Arc<StreamWrapper> {
+        if !self.args.iter().any(|a| a.contains(BUCKET_PREFIX)) {
+            return self.wr.clone();
+        }
+
+        let mut br = Record::empty_hash();
+        for (path, v) in bucket {
+            br.set_path(path, v.clone());
+        }
+
+        let mut args: Vec<String> = self.args.iter().map(|a| {
+            let mut ret = String::new();
+            let mut rest = &a[..];
+            while let Some(i) = rest.find(BUCKET_PREFIX) {
+                ret.push_str(&rest[0..i]);
+                rest = &rest[(i + BUCKET_PREFIX.len())..];
+                let j = rest.find("}}").unwrap_or_else(|| panic!("Unterminated {{{{bucket:...}}}} in {}", a));
+                ret.push_str(&br.get_path(&rest[0..j]).pretty_string());
+                rest = &rest[(j + 2)..];
+            }
+            ret.push_str(rest);
+            return ret;
+        }).collect();
+
+        let name = args.remove(0);
+        let op = super::REGISTRY.find(&name, &[]);
+        return Arc::new(op.parse(&mut args));
+    }
 }
@@ -0,0 +1,387 @@
+use std::sync::Arc;
+use stream::Entry;
+use stream::Stream;
+use super::REGISTRY;
+use super::StreamWrapper;
+
+fn parse(args: &[&str]) -> StreamWrapper {
+    let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let name = args.remove(0);
+    let op = REGISTRY.find(&name, &[]).parse(&mut args);
+    assert!(args.is_empty());
+    return op;
+}
+
+fn stream(args: &[&str]) -> Stream {
+    return parse(args).stream();
+}
+
+fn feed(s: &mut Stream, lines: &[&str], out: &mut Vec<String>) {
+    s.write(Entry::Bof(Arc::from("-")), &mut |e| collect(out, e));
+    for line in lines {
+        s.write(Entry::Line(Arc::from(*line)), &mut |e| collect(out, e));
+    }
+}
+
+fn collect(out: &mut Vec<String>, e: Entry) -> bool {
+    match e {
+        Entry::Bof(_file) => {
+        }
+        Entry::Record(r) => {
+            out.push(r.deparse());
+        }
+        Entry::Line(line) => {
+            out.push(line.to_string());
+        }
+    }
+    return true;
+}
+
+fn run(args: &[&str], lines: &[&str]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut s = stream(args);
+    feed(&mut s, lines, &mut out);
+    s.close(&mut |e| collect(&mut out, e));
+    return out;
+}
+
+#[test]
+fn test_resume_order() {
+    let input = [r#"{"k":"c"}"#, r#"{"k":"b"}"#, r#"{"k":"a"}"#, r#"{"k":"a"}"#, r#"{"k":"d"}"#, r#"{"k":"e"}"#];
+    let cp = std::env::temp_dir().join(format!("r4-test-resume-{}", std::process::id()));
+    let cp = cp.to_str().unwrap();
+
+    // "crash" (drop without closing) after the first four records
+    let mut s = stream(&["collate", "-k", "k", "-a", "ct=count", "--checkpoint", cp, "--checkpoint-every", "1"]);
+    feed(&mut s, &input[0..4], &mut Vec::new());
+    drop(s);
+
+    let expected = run(&["collate", "-k", "k", "-a", "ct=count"], &input);
+    for _ in 0..4 {
+        let resumed = run(&["collate", "-k", "k", "-a", "ct=count", "--resume", cp], &input);
+        assert_eq!(resumed, expected);
+    }
+    std::fs::remove_file(cp).unwrap();
+}
+
+#[test]
+fn test_multiplex_threads_refused() {
+    let mut s = stream(&["multiplex", "--threads", "2", "-k", "k", "--", "xform", "{}"]);
+    s.write(Entry::Bof(Arc::from("-")), &mut |_e| true);
+    let mut ct = 0;
+    let mut refused = false;
+    for i in 0..100000 {
+        let line = format!(r#"{{"k":{}}}"#, i % 5);
+        if !s.write(Entry::Line(Arc::from(line)), &mut |_e| { ct += 1; return false; }) {
+            refused = true;
+            break;
+        }
+    }
+    assert!(refused);
+    s.close(&mut |_e| { ct += 1; return false; });
+    assert_eq!(ct, 1);
+}
+
+#[test]
+fn test_sort_spill_existing() {
+    let dir = std::env::temp_dir().join(format!("r4-test-spill-{}", std::process::id()));
+    std::fs::create_dir(&dir).unwrap();
+    let victim = dir.join("victim");
+    std::fs::write(&victim, "keep").unwrap();
+    // links where run 7 would go, were names not checked
+    for attempt in 0..2 {
+        let link = dir.join(format!("r4-sort-{}-7-{}", std::process::id(), attempt));
+        std::os::unix::fs::symlink(&victim, &link).unwrap();
+    }
+
+    let (mut f, spill) = super::sort::Run::create(&dir, 7);
+    assert_eq!(spill.path(), &*dir.join(format!("r4-sort-{}-7-2", std::process::id())));
+    std::io::Write::write_all(&mut f, b"run").unwrap();
+    assert_eq!(std::fs::read_to_string(&victim).unwrap(), "keep");
+    drop(spill);
+    // only what we put there is left
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+    let input = [r#"{"k":3}"#, r#"{"k":5}"#, r#"{"k":1}"#, r#"{"k":4}"#, r#"{"k":2}"#];
+    let out = run(&["sort", "-n", "k", "--spill", "2", "--tmpdir", dir.to_str().unwrap()], &input);
+    assert_eq!(out, vec![r#"{"k":1}"#, r#"{"k":2}"#, r#"{"k":3}"#, r#"{"k":4}"#, r#"{"k":5}"#]);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sort_nulls() {
+    let input = [r#"{"k":"b"}"#, r#"{"k":""}"#, r#"{"k":null}"#, r#"{"k":"a"}"#];
+    // lexical ties null with "" as it always has
+    let out = run(&["sort", "-l", "k"], &input);
+    assert_eq!(out, vec![r#"{"k":""}"#, r#"{"k":null}"#, r#"{"k":"a"}"#, r#"{"k":"b"}"#]);
+    let out = run(&["sort", "-s", "nullsfirst,k", "-l", "k"], &input);
+    assert_eq!(out, vec![r#"{"k":null}"#, r#"{"k":""}"#, r#"{"k":"a"}"#, r#"{"k":"b"}"#]);
+    let out = run(&["sort", "-s", "nullslast,k", "-l", "k"], &input);
+    assert_eq!(out, vec![r#"{"k":""}"#, r#"{"k":"a"}"#, r#"{"k":"b"}"#, r#"{"k":null}"#]);
+}
+
+#[test]
+fn test_sort_expr_reverse() {
+    let input = [r#"{"a":1,"b":5}"#, r#"{"a":3,"b":1}"#, r#"{"a":2,"b":2}"#];
+    let out = run(&["sort", "-s", "numeric_expr,{{a}} * {{b}}"], &input);
+    assert_eq!(out, vec![r#"{"a":3,"b":1}"#, r#"{"a":2,"b":2}"#, r#"{"a":1,"b":5}"#]);
+    let out = run(&["sort", "-s", "-numeric_expr,{{a}} * {{b}}"], &input);
+    assert_eq!(out, vec![r#"{"a":1,"b":5}"#, r#"{"a":2,"b":2}"#, r#"{"a":3,"b":1}"#]);
+    let out = run(&["sort", "-s", "-expr,{{a}}"], &input);
+    assert_eq!(out, vec![r#"{"a":3,"b":1}"#, r#"{"a":2,"b":2}"#, r#"{"a":1,"b":5}"#]);
+    // a leading '-' in the code is just negation, by -2, 0, 4
+    let out = run(&["sort", "-s", "numeric_expr,-{{a}} + {{b}}"], &input);
+    assert_eq!(out, vec![r#"{"a":3,"b":1}"#, r#"{"a":2,"b":2}"#, r#"{"a":1,"b":5}"#]);
+    let out = run(&["sort", "-s", "-numeric_expr,-{{a}} + {{b}}"], &input);
+    assert_eq!(out, vec![r#"{"a":1,"b":5}"#, r#"{"a":2,"b":2}"#, r#"{"a":3,"b":1}"#]);
+}
+
+#[test]
+fn test_sort_shuffle_spill() {
+    let input: Vec<_> = (0..50).map(|i| format!(r#"{{"k":{}}}"#, i)).collect();
+    let input: Vec<_> = input.iter().map(|l| l as &str).collect();
+    let expected = run(&["sort", "-s", "shuffle,7"], &input);
+    assert_eq!(run(&["sort", "-s", "shuffle,7", "--spill", "4"], &input), expected);
+    let expected = run(&["sort", "-s", "shuffle,7", "--partial", "5"], &input);
+    assert_eq!(run(&["sort", "-s", "shuffle,7", "--partial", "5", "--spill", "4"], &input), expected);
+}
+
+#[test]
+#[should_panic(expected = "sample -k only works with -n")]
+fn test_sample_rate_keys() {
+    parse(&["sample", "--rate", "0.5", "-k", "k"]);
+}
+
+#[test]
+#[should_panic(expected = "sample needs exactly one of -n and --rate")]
+fn test_sample_n_rate() {
+    parse(&["sample", "-n", "2", "--rate", "0.5"]);
+}
+
+fn db_file(name: &str, lines: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("r4-test-{}-{}", name, std::process::id()));
+    std::fs::write(&path, lines.iter().map(|l| format!("{}\n", l)).collect::<String>()).unwrap();
+    return path.to_str().unwrap().to_string();
+}
+
+fn panic_message<F: FnOnce() + std::panic::UnwindSafe>(f: F) -> String {
+    let e = std::panic::catch_unwind(f).unwrap_err();
+    if let Some(s) = e.downcast_ref::<String>() {
+        return s.clone();
+    }
+    return e.downcast_ref::<&str>().unwrap().to_string();
+}
+
+#[test]
+fn test_join_merge_checks() {
+    let db = db_file("join-merge-checks", &[r#"{"k":1}"#, r#"{"k":2}"#]);
+
+    let msg = panic_message(|| {
+        parse(&["join", "-s", "numeric,k", "--on", "k", "k", "--db", &db]);
+    });
+    assert_eq!(msg, "join -s only works with --merge");
+
+    let msg = panic_message(|| {
+        run(&["join", "--merge", "--on", "k", "k", "--db", &db], &[r#"{"k":2}"#, r#"{"k":1}"#]);
+    });
+    assert_eq!(msg, r#"join --merge input isn't sorted, key {"k":1} came after {"k":2}"#);
+
+    std::fs::remove_file(&db).unwrap();
+}
+
+#[test]
+fn test_join_unique_outer() {
+    let db = db_file("join-unique-outer", &[r#"{"k":1,"x":"a"}"#, r#"{"k":1,"x":"b"}"#, r#"{"k":2,"x":"c"}"#, r#"{"k":3,"x":"d"}"#]);
+    let input = [r#"{"k":1}"#, r#"{"k":2}"#];
+    // the ambiguous k = 1 db records were matched, just not used
+    let expected = vec![r#"{"k":1}"#, r#"{"k":2,"x":"c"}"#, r#"{"k":3,"x":"d"}"#];
+    assert_eq!(run(&["join", "--unique", "--outer", "--on", "k", "k", "--db", &db], &input), expected);
+    assert_eq!(run(&["join", "--merge", "-s", "numeric,k", "--unique", "--outer", "--on", "k", "k", "--db", &db], &input), expected);
+
+    let msg = panic_message(|| {
+        run(&["join", "--assert-unique", "--on", "k", "k", "--db", &db], &input);
+    });
+    assert_eq!(msg, r#"join --assert-unique failed, key {"k":1} matched 2 db records"#);
+
+    std::fs::remove_file(&db).unwrap();
+}
+
+#[test]
+fn test_twindow() {
+    let input = [r#"{"t":0}"#, r#"{"t":5}"#, r#"{"t":10}"#, r#"{"t":3}"#, r#"{"t":25}"#, r#"{"t":12}"#, r#"{"t":9}"#];
+    // t = 10 opens [10, 20) rather than landing in [0, 10), and with no
+    // lateness closes [0, 10) so 3 is dropped, as are 12 and 9 after t = 25
+    assert_eq!(run(&["collate", "-c", "twindow,t,10", "-a", "l=array,t"], &input), vec![
+        r#"{"l":[0,5],"window_end":10,"window_start":0}"#,
+        r#"{"l":[10],"window_end":20,"window_start":10}"#,
+        r#"{"l":[25],"window_end":30,"window_start":20}"#,
+    ]);
+    // a watermark of 25 - 6 keeps [10, 20) open for 12 but 9 is too late
+    assert_eq!(run(&["collate", "-c", "twindow,t,10,6", "-a", "l=array,t"], &input), vec![
+        r#"{"l":[0,5,3],"window_end":10,"window_start":0}"#,
+        r#"{"l":[10,12],"window_end":20,"window_start":10}"#,
+        r#"{"l":[25],"window_end":30,"window_start":20}"#,
+    ]);
+}
+
+#[test]
+fn test_time_window_sizes() {
+    assert_eq!(panic_message(|| { parse(&["collate", "-c", "twindow,t,0", "-a", "ct=count"]); }), "twindow needs a positive size");
+    assert_eq!(panic_message(|| { parse(&["collate", "-c", "swindow,t,-10,5", "-a", "ct=count"]); }), "swindow needs a positive size");
+    assert_eq!(panic_message(|| { parse(&["collate", "-c", "swindow,t,10,0", "-a", "ct=count"]); }), "swindow needs a positive slide");
+}
+
+#[test]
+fn test_session() {
+    let input = [r#"{"u":"a","t":0}"#, r#"{"u":"b","t":1}"#, r#"{"u":"a","t":8}"#, r#"{"u":"a","t":-2}"#, r#"{"u":"b","t":30}"#, r#"{"u":"a","t":35}"#];
+    assert_eq!(run(&["collate", "-c", "ksession,u,t,10", "-a", "l=array,t"], &input), vec![
+        // b's gap split
+        r#"{"l":[1],"session_end":1,"session_index":0,"session_start":1,"u":"b"}"#,
+        // a idled out by b's t = 30, with the late -2 merged in
+        r#"{"l":[0,8,-2],"session_end":8,"session_index":0,"session_start":-2,"u":"a"}"#,
+        r#"{"l":[30],"session_end":30,"session_index":1,"session_start":30,"u":"b"}"#,
+        r#"{"l":[35],"session_end":35,"session_index":1,"session_start":35,"u":"a"}"#,
+    ]);
+    assert_eq!(run(&["collate", "-c", "session,t,10", "-a", "ct=count"], &input), vec![
+        r#"{"ct":4,"session_end":8,"session_index":0,"session_start":-2}"#,
+        r#"{"ct":2,"session_end":35,"session_index":1,"session_start":30}"#,
+    ]);
+}
+
+#[test]
+fn test_multiplex_bucket_args() {
+    let input = [r#"{"k":"a"}"#, r#"{"k":"b\"c"}"#, r#"{"k":1}"#, r#"{"k":{"x":1}}"#];
+    // spliced verbatim, non-strings as JSON
+    assert_eq!(run(&["multiplex", "-k", "k", "--", "provenance", "--ok", "src_{{bucket:k}}", "--", "xform", "{}"], &input), vec![
+        r#"{"k":"a","src_a":{"k":"a"}}"#,
+        r#"{"k":"b\"c","src_b\"c":{"k":"b\"c"}}"#,
+        r#"{"k":1,"src_1":{"k":1}}"#,
+        r#"{"k":{"x":1},"src_{\"x\":1}":{"k":{"x":1}}}"#,
+    ]);
+}
+
+fn run_files(args: &[&str], files: &[&[&str]]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut s = stream(args);
+    for lines in files {
+        feed(&mut s, lines, &mut out);
+    }
+    s.close(&mut |e| collect(&mut out, e));
+    return out;
+}
+
+#[test]
+fn test_window() {
+    let a: &[&str] = &[r#"{"x":0}"#, r#"{"x":1}"#, r#"{"x":2}"#, r#"{"x":3}"#, r#"{"x":4}"#];
+    let b: &[&str] = &[r#"{"x":5}"#, r#"{"x":6}"#];
+    let window = |spec| run_files(&["collate", "-c", spec, "-a", "l=array,x"], &[a, b]);
+
+    // no metadata unless asked for
+    assert_eq!(window("window,4"), vec![r#"{"l":[0,1,2,3]}"#, r#"{"l":[1,2,3,4]}"#]);
+
+    // step past size skips records, and b is too short to fill a window
+    assert_eq!(window("window,2,3,pos"), vec![
+        r#"{"l":[0,1],"window_index":0,"window_pos_end":2,"window_pos_start":0}"#,
+        r#"{"l":[3,4],"window_index":1,"window_pos_end":5,"window_pos_start":3}"#,
+        r#"{"l":[5,6],"window_index":0,"window_pos_end":2,"window_pos_start":0}"#,
+    ]);
+
+    // partials at b's Bof and at close
+    assert_eq!(window("window,3,2,partial,pos"), vec![
+        r#"{"l":[0,1,2],"window_index":0,"window_pos_end":3,"window_pos_start":0}"#,
+        r#"{"l":[2,3,4],"window_index":1,"window_pos_end":5,"window_pos_start":2}"#,
+        r#"{"l":[4],"window_index":2,"window_pos_end":5,"window_pos_start":4}"#,
+        r#"{"l":[5,6],"window_index":0,"window_pos_end":2,"window_pos_start":0}"#,
+    ]);
+
+    // windows span files and only partials at close
+    assert_eq!(window("window,3,2,partial,keep-bof,pos"), vec![
+        r#"{"l":[0,1,2],"window_index":0,"window_pos_end":3,"window_pos_start":0}"#,
+        r#"{"l":[2,3,4],"window_index":1,"window_pos_end":5,"window_pos_start":2}"#,
+        r#"{"l":[4,5,6],"window_index":2,"window_pos_end":7,"window_pos_start":4}"#,
+        r#"{"l":[6],"window_index":3,"window_pos_end":7,"window_pos_start":6}"#,
+    ]);
+    assert_eq!(window("window,3,1,keep-bof,partial"), vec![
+        r#"{"l":[0,1,2]}"#,
+        r#"{"l":[1,2,3]}"#,
+        r#"{"l":[2,3,4]}"#,
+        r#"{"l":[3,4,5]}"#,
+        r#"{"l":[4,5,6]}"#,
+        r#"{"l":[5,6]}"#,
+        r#"{"l":[6]}"#,
+    ]);
+}
+
+#[test]
+fn test_sort_rank() {
+    let input = [r#"{"k":3,"i":0}"#, r#"{"k":1,"i":1}"#, r#"{"k":3,"i":2}"#, r#"{"k":2,"i":3}"#, r#"{"k":1,"i":4}"#, r#"{"k":5,"i":5}"#];
+    // ties stay in input order
+    let expected = vec![r#"{"i":1,"k":1}"#, r#"{"i":4,"k":1}"#, r#"{"i":3,"k":2}"#, r#"{"i":0,"k":3}"#, r#"{"i":2,"k":3}"#, r#"{"i":5,"k":5}"#];
+    assert_eq!(run(&["sort", "-n", "k"], &input), expected);
+    assert_eq!(run(&["sort", "-n", "k", "--spill", "2"], &input), expected);
+
+    let expected = vec![
+        r#"{"i":1,"k":1,"p":16.666666666666668,"r":1}"#,
+        r#"{"i":4,"k":1,"p":16.666666666666668,"r":1}"#,
+        r#"{"i":3,"k":2,"p":50.0,"r":3}"#,
+        r#"{"i":0,"k":3,"p":66.66666666666667,"r":4}"#,
+        r#"{"i":2,"k":3,"p":66.66666666666667,"r":4}"#,
+        r#"{"i":5,"k":5,"p":100.0,"r":6}"#,
+    ];
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p"], &input), expected);
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p", "--competition"], &input), expected);
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p", "--spill", "2"], &input), expected);
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p", "-g", "x"], &input), expected);
+
+    // percentile stays competition
+    let expected = vec![
+        r#"{"i":1,"k":1,"p":16.666666666666668,"r":1}"#,
+        r#"{"i":4,"k":1,"p":16.666666666666668,"r":1}"#,
+        r#"{"i":3,"k":2,"p":50.0,"r":2}"#,
+        r#"{"i":0,"k":3,"p":66.66666666666667,"r":3}"#,
+        r#"{"i":2,"k":3,"p":66.66666666666667,"r":3}"#,
+        r#"{"i":5,"k":5,"p":100.0,"r":4}"#,
+    ];
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p", "--dense"], &input), expected);
+    assert_eq!(run(&["sort", "-n", "k", "--rank", "r", "--percentile", "p", "--dense", "--spill", "2"], &input), expected);
+}
+
+#[test]
+fn test_join_ranges() {
+    let db = db_file("join-ranges", &[r#"{"dt":10,"x":"a"}"#, r#"{"dt":20,"x":"b"}"#, r#"{"dt":20,"x":"c"}"#, r#"{"dt":30,"x":"d"}"#, r#"{"x":"e"}"#]);
+    // before the first, exact, ties for nearest and after the last
+    let input = [r#"{"t":5}"#, r#"{"t":10}"#, r#"{"t":15}"#, r#"{"t":25}"#, r#"{"t":35}"#];
+
+    assert_eq!(run(&["join", "--right", "--asof", "dt", "t", "--db", &db], &input), vec![
+        r#"{"dt":10,"t":10,"x":"a"}"#,
+        r#"{"dt":10,"t":15,"x":"a"}"#,
+        r#"{"dt":20,"t":25,"x":"b"}"#,
+        r#"{"dt":20,"t":25,"x":"c"}"#,
+        r#"{"dt":30,"t":35,"x":"d"}"#,
+        // null dt can never match
+        r#"{"x":"e"}"#,
+    ]);
+    assert_eq!(run(&["join", "--nearest", "dt", "t", "--db", &db], &input), vec![
+        r#"{"dt":10,"t":5,"x":"a"}"#,
+        r#"{"dt":10,"t":10,"x":"a"}"#,
+        r#"{"dt":10,"t":15,"x":"a"}"#,
+        r#"{"dt":20,"t":25,"x":"b"}"#,
+        r#"{"dt":20,"t":25,"x":"c"}"#,
+        r#"{"dt":30,"t":35,"x":"d"}"#,
+    ]);
+    std::fs::remove_file(&db).unwrap();
+
+    let db = db_file("join-intervals", &[r#"{"s":0,"e":10,"x":"a"}"#, r#"{"s":5,"e":15,"x":"b"}"#, r#"{"s":20,"e":30,"x":"c"}"#, r#"{"s":40,"x":"d"}"#]);
+    assert_eq!(run(&["join", "--right", "--interval", "s", "e", "t", "--db", &db], &input), vec![
+        // overlapping
+        r#"{"e":10,"s":0,"t":5,"x":"a"}"#,
+        r#"{"e":15,"s":5,"t":5,"x":"b"}"#,
+        // end is exclusive
+        r#"{"e":15,"s":5,"t":10,"x":"b"}"#,
+        r#"{"e":30,"s":20,"t":25,"x":"c"}"#,
+        r#"{"s":40,"x":"d"}"#,
+    ]);
+    std::fs::remove_file(&db).unwrap();
+}
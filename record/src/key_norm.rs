@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use super::JsonPrimitive;
+use super::Record;
+use super::RecordTrait;
+
+// Normalization of key values so that e.g.  1, "1" and 1.0 or "Host" and
+// "host " can be made to compare equal.  Steps are applied in the order
+// trim, num, str, ci.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(Default)]
+pub struct KeyNorm {
+    // trim whitespace from strings
+    pub trim: bool,
+    // numbers, and strings that parse as finite numbers (so not e.g.
+    // "nan" or "inf"), become integers if integral and floats otherwise
+    pub num: bool,
+    // primitives become strings (as by coerce_string())
+    pub stringify: bool,
+    // strings are lowercased
+    pub ci: bool,
+}
+
+impl KeyNorm {
+    // Parses a list of "trim", "num", "str", and "ci" separated by ',' or
+    // ':'.
+    pub fn parse(flags: &str) -> KeyNorm {
+        let mut norm = KeyNorm::default();
+        for flag in flags.split(|c| c == ',' || c == ':') {
+            match flag {
+                "trim" => norm.trim = true,
+                "num" | "numeric" => norm.num = true,
+                "str" | "string" => norm.stringify = true,
+                "ci" | "lc" => norm.ci = true,
+                "" => {
+                }
+                _ => panic!("Unknown key normalization {}", flag),
+            }
+        }
+        return norm;
+    }
+
+    pub fn apply(&self, mut r: Record) -> Record {
+        if self.trim {
+            if let Some(JsonPrimitive::String(s)) = r.maybe_primitive() {
+                r = Record::from(s.trim());
+            }
+        }
+        if self.num {
+            let n = match r.maybe_primitive() {
+                Some(JsonPrimitive::NumberI64(n)) => Some(n as f64),
+                Some(JsonPrimitive::NumberF64(n)) => Some(n.0),
+                Some(JsonPrimitive::String(s)) => s.parse().ok().filter(|n: &f64| n.is_finite()),
+                _ => None,
+            };
+            if let Some(n) = n {
+                r = match n.fract() == 0.0 && n.abs() < 9.0e18 {
+                    true => Record::from(n as i64),
+                    false => Record::from(n),
+                };
+            }
+        }
+        if self.stringify {
+            if r.maybe_primitive().is_some() {
+                r = Record::from(r.coerce_string());
+            }
+        }
+        if self.ci {
+            if let Some(JsonPrimitive::String(s)) = r.maybe_primitive() {
+                r = Record::from(Arc::<str>::from(s.to_lowercase()));
+            }
+        }
+        return r;
+    }
+}
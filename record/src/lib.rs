@@ -23,5 +23,8 @@ pub use self::record::Record;
 mod mrecord;
 pub use self::mrecord::MRecord;
 
+mod key_norm;
+pub use self::key_norm::KeyNorm;
+
 #[cfg(test)]
 mod tests;
@@ -143,6 +143,20 @@ impl Record {
         };
     }
 
+    pub fn maybe_array(&self) -> Option<&Vec<Record>> {
+        return match *self.0 {
+            RecordNode::Array(ref arr) => Some(arr),
+            _ => None,
+        };
+    }
+
+    pub fn maybe_hash(&self) -> Option<&BTreeMap<Arc<str>, Record>> {
+        return match *self.0 {
+            RecordNode::Hash(ref hash) => Some(hash),
+            _ => None,
+        };
+    }
+
     pub fn pretty_string(&self) -> String {
         return match *self.0 {
             RecordNode::Primitive(JsonPrimitive::String(ref s)) => s.to_string(),
@@ -1,3 +1,4 @@
+use super::KeyNorm;
 use super::Record;
 
 #[test]
@@ -29,3 +30,21 @@ fn test_set_path() {
     r.set_path("a/#2/b", Record::from("c"));
     assert_eq!(r.deparse(), "{\"a\":[null,null,{\"b\":\"c\"}],\"x\":[{\"y\":\"w\"}]}");
 }
+
+#[test]
+fn test_key_norm() {
+    let norm = |flags, s| KeyNorm::parse(flags).apply(Record::parse(s)).deparse();
+    assert_eq!(norm("", "\" Host \""), "\" Host \"");
+    assert_eq!(norm("trim,ci", "\" Host \""), "\"host\"");
+    assert_eq!(norm("num", "1.0"), "1");
+    assert_eq!(norm("num", "\"1\""), "1");
+    assert_eq!(norm("num", "\"1.5\""), "1.5");
+    assert_eq!(norm("num", "\"x\""), "\"x\"");
+    assert_eq!(norm("num", "\"nan\""), "\"nan\"");
+    assert_eq!(norm("num", "\"inf\""), "\"inf\"");
+    assert_eq!(norm("num", "\"-infinity\""), "\"-infinity\"");
+    assert_eq!(norm("num", "\"NaN\""), "\"NaN\"");
+    assert_eq!(norm("num:str", "1.0"), "\"1\"");
+    assert_eq!(norm("str", "true"), "\"true\"");
+    assert_eq!(norm("str", "null"), "\"\"");
+}
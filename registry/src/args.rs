@@ -7,6 +7,10 @@ pub trait RegistryArgs {
 
     fn argct() -> usize;
     fn parse(args: &[&str]) -> Self::Val;
+
+    fn max_argct() -> usize {
+        return Self::argct();
+    }
 }
 
 pub enum ZeroArgs {
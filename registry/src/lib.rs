@@ -5,9 +5,10 @@ use self::args::RegistryArgs;
 
 use opts::parser::OptParserView;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct Registry<R> {
-    map: HashMap<String, (usize, Box<Fn(&[&str]) -> R + Send + Sync>)>,
+    map: HashMap<String, (usize, usize, Arc<Fn(&[&str]) -> R + Send + Sync>)>,
 }
 
 impl<R> Default for Registry<R> {
@@ -20,17 +21,37 @@ impl<R> Default for Registry<R> {
 
 impl<R> Registry<R> {
     pub fn add<F: Fn(&[&str]) -> R + Send + Sync + 'static>(&mut self, name: &str, argct: usize, f: F) {
-        let prev = self.map.insert(name.to_string(), (argct, Box::new(f)));
+        self.add_optional(name, argct, argct, f);
+    }
+
+    // Takes anywhere from argct to max_argct args, although the multiple arg
+    // option forms (e.g.  "--c-window 5") only take argct.
+    pub fn add_optional<F: Fn(&[&str]) -> R + Send + Sync + 'static>(&mut self, name: &str, argct: usize, max_argct: usize, f: F) {
+        let prev = self.map.insert(name.to_string(), (argct, max_argct, Arc::new(f)));
         assert!(prev.is_none(), "registry collision for {}", name);
     }
 
+    // Adds "<name><suffix>" for everything registered so far, taking
+    // extra_argct leading args which are handed to f along with the result
+    // of the original on the remaining args.
+    pub fn add_wrapped<F: Fn(&[&str], R) -> R + Send + Sync + 'static>(&mut self, suffix: &str, extra_argct: usize, f: F) where R: 'static {
+        let f = Arc::new(f);
+        let prevs: Vec<_> = self.map.iter().map(|(name, (argct, max_argct, init))| (name.clone(), *argct, *max_argct, init.clone())).collect();
+        for (name, argct, max_argct, init) in prevs {
+            let f = f.clone();
+            self.add_optional(&format!("{}{}", name, suffix), extra_argct + argct, extra_argct + max_argct, move |args| {
+                return f(&args[0..extra_argct], init(&args[extra_argct..]));
+            });
+        }
+    }
+
     pub fn find(&self, name: &str, args: &[&str]) -> R {
         match self.map.get(name) {
             None => {
                 panic!("No implementation named {}", name);
             }
-            Some((argct, f)) => {
-                if args.len() != *argct {
+            Some((argct, max_argct, f)) => {
+                if args.len() < *argct || args.len() > *max_argct {
                     panic!("Wrong number of args for {}", name);
                 }
                 return f(args);
@@ -39,7 +60,7 @@ impl<R> Registry<R> {
     }
 
     pub fn labelled_multiple_options<'a, O: AsMut<Vec<(String, R)>> + 'static>(&'static self, opt: &mut OptParserView<'a, O>, prefixes: &[&str]) {
-        for (alias, (argct, f)) in &self.map {
+        for (alias, (argct, _max_argct, f)) in &self.map {
             let aliases: Vec<_> = prefixes.iter().map(|prefix| format!("{}-{}", prefix, alias)).collect();
             opt.match_n(aliases, argct + 1, move |rs, a| {
                 let mut iter = a.iter();
@@ -65,7 +86,7 @@ impl<R> Registry<R> {
     }
 
     pub fn multiple_options<'a, O: AsMut<Vec<R>> + 'static>(&'static self, opt: &mut OptParserView<'a, O>, prefixes: &[&str]) {
-        for (alias, (argct, f)) in &self.map {
+        for (alias, (argct, _max_argct, f)) in &self.map {
             let aliases: Vec<_> = prefixes.iter().map(|prefix| format!("{}-{}", prefix, alias)).collect();
             opt.match_n(aliases, *argct, move |rs, a| {
                 let a: Vec<_> = a.iter().map(|s| s as &str).collect();
@@ -88,6 +109,14 @@ impl<R> Registry<R> {
 #[macro_export]
 macro_rules! registry {
     {$r:ty, $($id:ident,)*} => {
+        registry! {
+            $r,
+            $($id,)*
+            => |_r| {
+            }
+        }
+    };
+    {$r:ty, $($id:ident,)* => $post:expr} => {
         $(
             pub mod $id;
         )*
@@ -97,9 +126,10 @@ macro_rules! registry {
                 let mut r = $crate::Registry::default();
                 $(
                     for name in <$id::Impl as $crate::Registrant<$r>>::names() {
-                        r.add(name, <$id::Impl as $crate::Registrant<$r>>::argct(), <$id::Impl as $crate::Registrant<$r>>::init);
+                        r.add_optional(name, <$id::Impl as $crate::Registrant<$r>>::argct(), <$id::Impl as $crate::Registrant<$r>>::max_argct(), <$id::Impl as $crate::Registrant<$r>>::init);
                     }
                 )*
+                ($post)(&mut r);
                 r
             };
         }
@@ -116,6 +146,10 @@ pub trait Registrant<R> {
         return Self::Args::argct();
     }
 
+    fn max_argct() -> usize {
+        return Self::Args::max_argct();
+    }
+
     fn init(args: &[&str]) -> R {
         return Self::init2(Self::Args::parse(args));
     }
@@ -0,0 +1,97 @@
+use executor::SharedStream;
+use registry::Registrant;
+use registry::Registry;
+use registry::args::RegistryArgs;
+use std::cmp::Reverse;
+use std::rc::Rc;
+use super::BoxedSort;
+use super::SortBe;
+use super::SortRegistrant;
+use super::SortSimpleBe;
+use super::bucket::KeySortBucket;
+use super::bucket::SortBucket;
+
+// Every simple sort also gets "<name>_expr,<code>" which sorts by the value
+// of code (as in eval) rather than a path, e.g.
+// "numeric_expr,{{end}} - {{start}}", and "expr,<code>" sorts the values as
+// typed does.  Reversing goes on the name rather than the code (where a
+// leading '-' is just negation), e.g.  "-lexical_expr,{{name}}".
+pub(crate) fn register(r: &mut Registry<BoxedSort>) {
+    add::<super::lexical::ImplSimpleBe>(r);
+    add::<super::lexical_ci::ImplSimpleBe>(r);
+    add::<super::natural::ImplSimpleBe>(r);
+    add::<super::numeric::ImplSimpleBe>(r);
+    add::<super::typed::ImplSimpleBe>(r);
+    add::<super::version::ImplSimpleBe>(r);
+    r.add("expr", 1, SortRegistrant::<SortBeFromSimpleExpr<super::typed::ImplSimpleBe, ExprArgs>>::init);
+    r.add("-expr", 1, SortRegistrant::<SortBeFromSimpleExpr<super::typed::ImplSimpleBe, ReverseExprArgs>>::init);
+}
+
+fn add<B: SortSimpleBe + 'static>(r: &mut Registry<BoxedSort>) {
+    for name in B::names() {
+        r.add(&format!("{}_expr", name), 1, SortRegistrant::<SortBeFromSimpleExpr<B, ExprArgs>>::init);
+        r.add(&format!("-{}_expr", name), 1, SortRegistrant::<SortBeFromSimpleExpr<B, ReverseExprArgs>>::init);
+    }
+}
+
+pub struct ExprArgsVal {
+    reverse: bool,
+    f: SharedStream,
+}
+
+pub enum ExprArgs {
+}
+
+impl RegistryArgs for ExprArgs {
+    type Val = ExprArgsVal;
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn parse(args: &[&str]) -> ExprArgsVal {
+        assert_eq!(1, args.len());
+        return ExprArgsVal {
+            reverse: false,
+            f: SharedStream::parse_inline(args[0], true),
+        };
+    }
+}
+
+pub enum ReverseExprArgs {
+}
+
+impl RegistryArgs for ReverseExprArgs {
+    type Val = ExprArgsVal;
+
+    fn argct() -> usize {
+        return 1;
+    }
+
+    fn parse(args: &[&str]) -> ExprArgsVal {
+        return ExprArgsVal {
+            reverse: true,
+            ..ExprArgs::parse(args)
+        };
+    }
+}
+
+pub struct SortBeFromSimpleExpr<B: SortSimpleBe, A: RegistryArgs<Val = ExprArgsVal>> {
+    _x: std::marker::PhantomData<(B, A)>,
+}
+
+impl<B: SortSimpleBe, A: RegistryArgs<Val = ExprArgsVal>> SortBe for SortBeFromSimpleExpr<B, A> {
+    type Args = A;
+
+    fn names() -> Vec<&'static str> {
+        return vec![];
+    }
+
+    fn new_bucket(a: &ExprArgsVal, next: Rc<Fn() -> Box<SortBucket>>) -> Box<SortBucket> {
+        let f = a.f.clone();
+        if a.reverse {
+            return KeySortBucket::new(move |r, _i| Reverse(B::get(f.call(r))), next);
+        }
+        return KeySortBucket::new(move |r, _i| B::get(f.call(r)), next);
+    }
+}
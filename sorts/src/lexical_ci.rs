@@ -0,0 +1,23 @@
+use record::Record;
+use record::RecordTrait;
+use super::SortBeFromSimple;
+use super::SortRegistrant;
+use super::SortSimpleBe;
+
+pub type Impl = SortRegistrant<ImplBe>;
+
+pub(crate) type ImplBe = SortBeFromSimple<ImplSimpleBe>;
+
+pub struct ImplSimpleBe;
+
+impl SortSimpleBe for ImplSimpleBe {
+    type T = String;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["ci", "lexci", "lexicalci"];
+    }
+
+    fn get(r: Record) -> String {
+        return r.coerce_string().to_lowercase();
+    }
+}
@@ -1,5 +1,6 @@
 #[macro_use]
 extern crate lazy_static;
+extern crate executor;
 extern crate record;
 extern crate rand;
 extern crate rand_chacha;
@@ -7,10 +8,13 @@ extern crate rand_chacha;
 extern crate registry;
 
 pub mod bucket;
+mod expr;
 use self::bucket::KeySortBucket;
 use self::bucket::SortBucket;
 
+use record::JsonPrimitive;
 use record::Record;
+use record::RecordTrait;
 use registry::Registrant;
 use registry::args::OneStringArgs;
 use registry::args::RegistryArgs;
@@ -23,8 +27,15 @@ pub type BoxedSort = Box<SortInbox>;
 registry! {
     BoxedSort,
     lexical,
+    lexical_ci,
+    natural,
+    nulls_first,
+    nulls_last,
     numeric,
     shuffle,
+    typed,
+    version,
+    => expr::register
 }
 
 pub trait SortBe {
@@ -105,3 +116,10 @@ impl<B: SortSimpleBe> SortBe for SortBeFromSimple<B> {
         return KeySortBucket::new(move |r, _i| B::get(r.get_path(&key)), next);
     }
 }
+
+pub fn is_null(r: &Record) -> bool {
+    return match r.maybe_primitive() {
+        Some(JsonPrimitive::Null()) => true,
+        _ => false,
+    };
+}
@@ -0,0 +1,54 @@
+use record::Record;
+use record::RecordTrait;
+use std::sync::Arc;
+use super::SortBeFromSimple;
+use super::SortRegistrant;
+use super::SortSimpleBe;
+
+// Runs of digits compare as numbers (of any length) and come before text.
+#[derive(Clone)]
+#[derive(Eq)]
+#[derive(Ord)]
+#[derive(PartialEq)]
+#[derive(PartialOrd)]
+pub enum NaturalPart {
+    Number(usize, Arc<str>),
+    Text(Arc<str>),
+}
+
+pub fn natural_key(s: &str) -> Vec<NaturalPart> {
+    let mut ret = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let digit = c.is_ascii_digit();
+        let end = rest.find(|c: char| c.is_ascii_digit() != digit).unwrap_or(rest.len());
+        let (part, rest2) = rest.split_at(end);
+        rest = rest2;
+        if digit {
+            let part = part.trim_start_matches('0');
+            ret.push(NaturalPart::Number(part.len(), Arc::from(part)));
+        }
+        else {
+            ret.push(NaturalPart::Text(Arc::from(part)));
+        }
+    }
+    return ret;
+}
+
+pub type Impl = SortRegistrant<ImplBe>;
+
+pub(crate) type ImplBe = SortBeFromSimple<ImplSimpleBe>;
+
+pub struct ImplSimpleBe;
+
+impl SortSimpleBe for ImplSimpleBe {
+    type T = Vec<NaturalPart>;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["nat", "natural"];
+    }
+
+    fn get(r: Record) -> Vec<NaturalPart> {
+        return natural_key(&r.coerce_string());
+    }
+}
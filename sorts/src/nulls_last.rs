@@ -0,0 +1,27 @@
+use registry::args::OneStringArgs;
+use std::rc::Rc;
+use std::sync::Arc;
+use super::SortBe;
+use super::SortRegistrant;
+use super::bucket::KeySortBucket;
+use super::bucket::SortBucket;
+
+pub type Impl = SortRegistrant<ImplBe>;
+
+pub struct ImplBe;
+
+// Only sorts missing/null values after everything else, leaving the rest
+// tied for any further sorts, which see the nulls as they always have (e.g.
+// lexical as "").
+impl SortBe for ImplBe {
+    type Args = OneStringArgs;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["nullslast"];
+    }
+
+    fn new_bucket(a: &Arc<str>, next: Rc<Fn() -> Box<SortBucket>>) -> Box<SortBucket> {
+        let key = a.clone();
+        return KeySortBucket::new(move |r, _i| super::is_null(&r.get_path(&key)), next);
+    }
+}
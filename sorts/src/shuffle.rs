@@ -1,5 +1,7 @@
 use rand::Rng;
-use registry::args::ZeroArgs;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use registry::args::RegistryArgs;
 use std::cmp::Ordering;
 use std::rc::Rc;
 use std::sync::Mutex;
@@ -8,9 +10,16 @@ use super::SortRegistrant;
 use super::bucket::KeySortBucket;
 use super::bucket::SortBucket;
 
+// Where a key's bytes come from, with a seed each record gets its own ChaCha
+// stream (by index) so the result doesn't depend on the order comparisons
+// happen to be made in.
+enum KeySource {
+    Thread,
+    Seeded(ChaChaRng),
+}
+
 #[derive(Clone)]
-#[derive(Default)]
-struct RandomSortKey(usize, Rc<Mutex<Vec<u8>>>);
+struct RandomSortKey(usize, Rc<Mutex<(Vec<u8>, KeySource)>>);
 
 impl PartialEq for RandomSortKey {
     fn eq(&self, other: &Self) -> bool {
@@ -46,16 +55,47 @@ impl Ord for RandomSortKey {
 }
 
 impl RandomSortKey {
-    fn new(i: usize) -> Self {
-        return RandomSortKey(i, Rc::new(Mutex::new(Vec::new())));
+    fn new(i: usize, seed: Option<u64>) -> Self {
+        let source = match seed {
+            Some(seed) => {
+                let mut rng = ChaChaRng::seed_from_u64(seed);
+                rng.set_stream(i as u64);
+                KeySource::Seeded(rng)
+            },
+            None => KeySource::Thread,
+        };
+        return RandomSortKey(i, Rc::new(Mutex::new((Vec::new(), source))));
     }
 
     fn at(&self, i: usize) -> u8 {
         let mut mg = self.1.lock().unwrap();
-        while i >= mg.len() {
-            mg.push(rand::thread_rng().gen());
+        let (ref mut bytes, ref mut source) = *mg;
+        while i >= bytes.len() {
+            bytes.push(match source {
+                KeySource::Thread => rand::thread_rng().gen(),
+                KeySource::Seeded(rng) => rng.gen(),
+            });
         }
-        return mg[i];
+        return bytes[i];
+    }
+}
+
+pub enum ShuffleArgs {
+}
+
+impl RegistryArgs for ShuffleArgs {
+    type Val = Option<u64>;
+
+    fn argct() -> usize {
+        return 0;
+    }
+
+    fn max_argct() -> usize {
+        return 1;
+    }
+
+    fn parse(args: &[&str]) -> Option<u64> {
+        return args.get(0).map(|s| s.parse().unwrap());
     }
 }
 
@@ -64,13 +104,14 @@ pub type Impl = SortRegistrant<ImplBe>;
 pub struct ImplBe;
 
 impl SortBe for ImplBe {
-    type Args = ZeroArgs;
+    type Args = ShuffleArgs;
 
     fn names() -> Vec<&'static str> {
         return vec!["shuffle"];
     }
 
-    fn new_bucket(_a: &(), next: Rc<Fn() -> Box<SortBucket>>) -> Box<SortBucket> {
-        return KeySortBucket::new(|_r, i| RandomSortKey::new(i), next);
+    fn new_bucket(seed: &Option<u64>, next: Rc<Fn() -> Box<SortBucket>>) -> Box<SortBucket> {
+        let seed = *seed;
+        return KeySortBucket::new(move |_r, i| RandomSortKey::new(i, seed), next);
     }
 }
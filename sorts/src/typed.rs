@@ -0,0 +1,60 @@
+use record::F64SortDishonorProxy;
+use record::JsonPrimitive;
+use record::Record;
+use record::RecordTrait;
+use std::sync::Arc;
+use super::SortBeFromSimple;
+use super::SortRegistrant;
+use super::SortSimpleBe;
+
+// Orders by type first (null, bool, number, string, array, hash) and then
+// within type, numbers numerically regardless of int vs. float and arrays
+// and hashes elementwise.
+#[derive(Clone)]
+#[derive(Eq)]
+#[derive(Ord)]
+#[derive(PartialEq)]
+#[derive(PartialOrd)]
+pub enum TypedKey {
+    Null,
+    Bool(bool),
+    Number(F64SortDishonorProxy),
+    String(Arc<str>),
+    Array(Vec<TypedKey>),
+    Hash(Vec<(Arc<str>, TypedKey)>),
+}
+
+pub fn typed_key(r: &Record) -> TypedKey {
+    if let Some(p) = r.maybe_primitive() {
+        return match p {
+            JsonPrimitive::Null() => TypedKey::Null,
+            JsonPrimitive::Bool(b) => TypedKey::Bool(b),
+            JsonPrimitive::NumberI64(n) => TypedKey::Number(F64SortDishonorProxy(n as f64)),
+            JsonPrimitive::NumberF64(f) => TypedKey::Number(F64SortDishonorProxy(f.0)),
+            JsonPrimitive::String(s) => TypedKey::String(s),
+        };
+    }
+    if let Some(arr) = r.maybe_array() {
+        return TypedKey::Array(arr.iter().map(typed_key).collect());
+    }
+    let hash = r.expect_hash();
+    return TypedKey::Hash(hash.iter().map(|(k, v)| (k.clone(), typed_key(v))).collect());
+}
+
+pub type Impl = SortRegistrant<ImplBe>;
+
+pub(crate) type ImplBe = SortBeFromSimple<ImplSimpleBe>;
+
+pub struct ImplSimpleBe;
+
+impl SortSimpleBe for ImplSimpleBe {
+    type T = TypedKey;
+
+    fn names() -> Vec<&'static str> {
+        return vec!["typed", "json"];
+    }
+
+    fn get(r: Record) -> TypedKey {
+        return typed_key(&r);
+    }
+}
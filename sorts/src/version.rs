@@ -0,0 +1,41 @@
+use record::Record;
+use record::RecordTrait;
+use super::SortBeFromSimple;
+use super::SortRegistrant;
+use super::SortSimpleBe;
+use super::natural::NaturalPart;
+use super::natural::natural_key;
+
+pub type Impl = SortRegistrant<ImplBe>;
+
+pub(crate) type ImplBe = SortBeFromSimple<ImplSimpleBe>;
+
+pub struct ImplSimpleBe;
+
+// Semver-ish: dot separated components compared naturally, with anything
+// after a '-' a pre-release which comes before the plain version, and
+// anything after a '+' ignored.
+impl SortSimpleBe for ImplSimpleBe {
+    type T = (Vec<Vec<NaturalPart>>, bool, Vec<Vec<NaturalPart>>);
+
+    fn names() -> Vec<&'static str> {
+        return vec!["ver", "version"];
+    }
+
+    fn get(r: Record) -> (Vec<Vec<NaturalPart>>, bool, Vec<Vec<NaturalPart>>) {
+        let s = r.coerce_string();
+        let s = match s.find('+') {
+            Some(i) => &s[0..i],
+            None => &s[..],
+        };
+        let (core, pre) = match s.find('-') {
+            Some(i) => (&s[0..i], Some(&s[(i + 1)..])),
+            None => (s, None),
+        };
+        let parts = |s: &str| s.split('.').map(natural_key).collect();
+        return match pre {
+            Some(pre) => (parts(core), false, parts(pre)),
+            None => (parts(core), true, vec![]),
+        };
+    }
+}